@@ -1 +1,5 @@
 pub static RESPONSE_BLOCK_SIZE: usize = 256;
+// shares of the payees are in basis points and have to add up to this
+pub static TOTAL_SHARES: u128 = 10000;
+// a single purchase on a bonding curve can't cross more price steps than this
+pub static MAX_CURVE_STEPS: u32 = 1000;
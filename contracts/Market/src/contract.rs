@@ -1,11 +1,16 @@
 use std::vec;
 
-use crate::constants::RESPONSE_BLOCK_SIZE;
-use crate::msg::{ConfigResponse, HandleMessage, InitMsg, QueryMessage, TotalRaisedResponse};
-use crate::state::{config, config_read, State};
+use crate::constants::{MAX_CURVE_STEPS, RESPONSE_BLOCK_SIZE, TOTAL_SHARES};
+use crate::msg::{
+    ClaimableResponse, ConfigResponse, HandleMessage, InitMsg, PayeesResponse, QueryMessage,
+    QuoteBuyResponse, TotalMintedResponse, TotalRaisedResponse, TotalWithdrawnResponse,
+};
+use crate::state::{
+    config, config_read, read_claimable, write_claimable, ExchangeRate, Payee, Pricing, State,
+};
 use cosmwasm_std::{
-    log, to_binary, Api, Binary, Env, Extern, HandleResponse, InitResponse, Querier, StdError,
-    StdResult, Storage, Uint128,
+    log, to_binary, Api, BankMsg, Binary, Coin, CosmosMsg, Env, Extern, HandleResponse, HumanAddr,
+    InitResponse, Querier, StdError, StdResult, Storage, Uint128,
 };
 use secret_toolkit::snip20;
 
@@ -14,11 +19,30 @@ pub fn init<S: Storage, A: Api, Q: Querier>(
     env: Env,
     msg: InitMsg,
 ) -> StdResult<InitResponse> {
+    check_exchange_rate(&msg.token_exchange_rate)?;
+    let pricing = msg.pricing.unwrap_or_default();
+    check_pricing(&pricing)?;
+    let token_info = snip20::token_info_query(
+        &deps.querier,
+        RESPONSE_BLOCK_SIZE,
+        msg.token_contract_hash.clone(),
+        msg.token_contract_address.clone(),
+    )?;
+
     let state = State {
         exchange_rate: msg.token_exchange_rate,
+        token_decimals: token_info.decimals,
         admin: msg.admin.unwrap_or(env.message.sender),
         contract_adress: msg.token_contract_address,
         total_raised: Uint128(0),
+        total_withdrawn: Uint128(0),
+        payees: vec![],
+        total_claimable: Uint128(0),
+        paused: false,
+        step_price: base_price(&pricing),
+        step_filled: Uint128(0),
+        pricing,
+        total_minted: Uint128(0),
         contract_hash: msg.token_contract_hash,
     };
     config(&mut deps.storage).save(&state)?;
@@ -35,7 +59,21 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     msg: HandleMessage,
 ) -> StdResult<HandleResponse> {
     match msg {
-        HandleMessage::BuyFood {} => try_buy_food(deps, env),
+        HandleMessage::BuyFood { min_amount_out } => try_buy_food(deps, env, min_amount_out),
+        HandleMessage::Withdraw { amount, recipient } => try_withdraw(deps, env, amount, recipient),
+        HandleMessage::SetPayees { payees } => try_set_payees(deps, env, payees),
+        HandleMessage::Distribute {} => try_distribute(deps, env),
+        HandleMessage::Claim {} => try_claim(deps, env),
+        HandleMessage::SetExchangeRate { exchange_rate } => {
+            try_set_exchange_rate(deps, env, exchange_rate)
+        }
+        HandleMessage::SetTokenContract { address, hash } => {
+            try_set_token_contract(deps, env, address, hash)
+        }
+        HandleMessage::ChangeAdmin { address } => try_change_admin(deps, env, address),
+        HandleMessage::SetPricing { pricing } => try_set_pricing(deps, env, pricing),
+        HandleMessage::Pause {} => try_set_paused(deps, env, true),
+        HandleMessage::Unpause {} => try_set_paused(deps, env, false),
     }
 }
 
@@ -46,6 +84,11 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
     match msg {
         QueryMessage::Config {} => to_binary(&query_config(deps)),
         QueryMessage::TotalRaised {} => to_binary(&query_total_raised(deps)),
+        QueryMessage::TotalWithdrawn {} => to_binary(&query_total_withdrawn(deps)),
+        QueryMessage::Payees {} => to_binary(&query_payees(deps)?),
+        QueryMessage::Claimable { address } => to_binary(&query_claimable(deps, address)?),
+        QueryMessage::TotalMinted {} => to_binary(&query_total_minted(deps)?),
+        QueryMessage::QuoteBuy { uscrt_amount } => to_binary(&query_quote_buy(deps, uscrt_amount)?),
     }
 }
 
@@ -57,6 +100,50 @@ pub fn query_total_raised<S: Storage, A: Api, Q: Querier>(
         amount: state.total_raised,
     })
 }
+pub fn query_total_withdrawn<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<TotalWithdrawnResponse> {
+    let state = config_read(&deps.storage).load()?;
+    Ok(TotalWithdrawnResponse {
+        amount: state.total_withdrawn,
+    })
+}
+pub fn query_payees<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<PayeesResponse> {
+    let state = config_read(&deps.storage).load()?;
+    Ok(PayeesResponse {
+        payees: state.payees,
+    })
+}
+pub fn query_claimable<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: HumanAddr,
+) -> StdResult<ClaimableResponse> {
+    Ok(ClaimableResponse {
+        amount: read_claimable(&deps.storage, &address)?,
+    })
+}
+pub fn query_total_minted<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<TotalMintedResponse> {
+    let state = config_read(&deps.storage).load()?;
+    Ok(TotalMintedResponse {
+        amount: state.total_minted,
+    })
+}
+pub fn query_quote_buy<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    uscrt_amount: Uint128,
+) -> StdResult<QuoteBuyResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let purchase = quote_purchase(&state, uscrt_amount.u128())?;
+    Ok(QuoteBuyResponse {
+        food_amount: Uint128(purchase.food),
+        cost: Uint128(purchase.cost),
+        refund: Uint128(uscrt_amount.u128() - purchase.cost),
+    })
+}
 pub fn query_config<S: Storage, A: Api, Q: Querier>(
     deps: &Extern<S, A, Q>,
 ) -> StdResult<ConfigResponse> {
@@ -64,6 +151,10 @@ pub fn query_config<S: Storage, A: Api, Q: Querier>(
     Ok(ConfigResponse {
         token_contract_hash: state.contract_hash,
         exchange_rate: state.exchange_rate,
+        token_decimals: state.token_decimals,
+        paused: state.paused,
+        pricing: state.pricing,
+        step_price: state.step_price,
         token_contract_address: state.contract_adress,
         admin: state.admin,
     })
@@ -72,8 +163,12 @@ pub fn query_config<S: Storage, A: Api, Q: Querier>(
 pub fn try_buy_food<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
+    min_amount_out: Option<Uint128>,
 ) -> StdResult<HandleResponse> {
     let mut state = config_read(&deps.storage).load()?;
+    if state.paused {
+        return Err(StdError::generic_err("The sale of food is paused"));
+    }
 
     let mut total_coins_sent = Uint128::zero();
     for coin in env.message.sent_funds.iter() {
@@ -82,60 +177,574 @@ pub fn try_buy_food<S: Storage, A: Api, Q: Querier>(
                 "Only uscrt is supported. Invalid token sent. ",
             ));
         }
-        total_coins_sent = total_coins_sent + coin.amount;
+        total_coins_sent += coin.amount;
     }
     if total_coins_sent.is_zero() {
         return Err(StdError::generic_err("No coins sent"));
     }
 
-    state.total_raised += total_coins_sent;
-    config(&mut deps.storage).save(&state)?;
+    let purchase = quote_purchase(&state, total_coins_sent.u128())?;
+    let (amount_to_mint, cost) = (purchase.food, purchase.cost);
+    if amount_to_mint == 0 {
+        return Err(StdError::generic_err(
+            "Not enough uscrt sent to buy the smallest unit of food",
+        ));
+    }
+    if let Some(min_amount_out) = min_amount_out {
+        if amount_to_mint < min_amount_out.u128() {
+            return Err(StdError::generic_err(format!(
+                "The uscrt sent buys {} food, less than the minimum of {}",
+                amount_to_mint, min_amount_out
+            )));
+        }
+    }
+    // the uscrt left over after rounding down the food is sent back
+    let refund = total_coins_sent.u128() - cost;
 
-    let amount_to_mint = Uint128(total_coins_sent.u128() * state.exchange_rate.u128());
+    state.total_raised += Uint128(cost);
+    state.total_minted += Uint128(amount_to_mint);
+    state.step_price = Uint128(purchase.step_price);
+    state.step_filled = Uint128(purchase.step_filled);
+    config(&mut deps.storage).save(&state)?;
 
     let mint_msg = snip20::mint_msg(
         env.message.sender.clone(),
-        amount_to_mint,
+        Uint128(amount_to_mint),
         None,
         RESPONSE_BLOCK_SIZE,
         state.contract_hash,
         state.contract_adress,
     )?;
+    let mut messages = vec![mint_msg];
+    if refund > 0 {
+        messages.push(send_uscrt(&env, &env.message.sender, Uint128(refund)));
+    }
 
     Ok(HandleResponse {
-        messages: vec![mint_msg],
+        messages,
         log: vec![
             log("action", "mint"),
-            log("amount", &total_coins_sent),
+            log("amount", cost),
+            log("food_amount", amount_to_mint),
+            log("refund", refund),
             log("recipient", env.message.sender.clone()),
         ],
         data: None,
     })
 }
 
+struct Purchase {
+    food: u128,
+    cost: u128,
+    // where the curve stands after the purchase
+    step_price: u128,
+    step_filled: u128,
+}
+
+// What the uscrt buy at the current pricing. The food is rounded down and the cost rounded up
+fn quote_purchase(state: &State, uscrt: u128) -> StdResult<Purchase> {
+    let (increase, growth, step) = match state.pricing {
+        Pricing::Flat {} => {
+            let (food, cost) = food_for_uscrt(&state.exchange_rate, state.token_decimals, uscrt)?;
+            return Ok(Purchase {
+                food,
+                cost,
+                step_price: state.step_price.u128(),
+                step_filled: state.step_filled.u128(),
+            });
+        }
+        Pricing::Linear { increase, step, .. } => (increase.u128(), 0, step.u128()),
+        Pricing::Exponential { growth, step, .. } => (0, growth as u128, step.u128()),
+    };
+
+    let overflow = || StdError::generic_err("The amount of food to mint is too large");
+    let unit = 10u128
+        .checked_pow(state.token_decimals as u32)
+        .ok_or_else(overflow)?;
+    let mut purchase = Purchase {
+        food: 0,
+        cost: 0,
+        step_price: state.step_price.u128(),
+        step_filled: state.step_filled.u128(),
+    };
+    let mut left = uscrt;
+
+    for _ in 0..MAX_CURVE_STEPS {
+        let price = purchase.step_price;
+        let room = step - purchase.step_filled;
+        let room_cost = room
+            .checked_mul(price)
+            .and_then(|total| total.checked_add(unit - 1))
+            .ok_or_else(overflow)?
+            / unit;
+
+        if room_cost > left {
+            // the rest of the uscrt buys only a part of the step
+            let food = left.checked_mul(unit).ok_or_else(overflow)? / price;
+            // food * price is at most left * unit, which didn't overflow
+            let cost = (food * price).checked_add(unit - 1).ok_or_else(overflow)? / unit;
+            purchase.food += food;
+            purchase.cost += cost;
+            purchase.step_filled += food;
+            return Ok(purchase);
+        }
+
+        purchase.food = purchase.food.checked_add(room).ok_or_else(overflow)?;
+        purchase.cost += room_cost;
+        left -= room_cost;
+        let rise = if growth == 0 {
+            increase
+        } else {
+            price
+                .checked_mul(growth)
+                .and_then(|rise| rise.checked_add(TOTAL_SHARES - 1))
+                .ok_or_else(overflow)?
+                / TOTAL_SHARES
+        };
+        purchase.step_price = price.checked_add(rise).ok_or_else(overflow)?;
+        purchase.step_filled = 0;
+    }
+
+    Err(StdError::generic_err(format!(
+        "Can't buy food across more than {} price steps at once",
+        MAX_CURVE_STEPS
+    )))
+}
+
+fn base_price(pricing: &Pricing) -> Uint128 {
+    match pricing {
+        Pricing::Flat {} => Uint128(0),
+        Pricing::Linear { base_price, .. } | Pricing::Exponential { base_price, .. } => *base_price,
+    }
+}
+
+fn check_pricing(pricing: &Pricing) -> StdResult<()> {
+    let valid = match pricing {
+        Pricing::Flat {} => true,
+        Pricing::Linear {
+            base_price,
+            increase,
+            step,
+        } => !base_price.is_zero() && !increase.is_zero() && !step.is_zero(),
+        Pricing::Exponential {
+            base_price,
+            growth,
+            step,
+        } => !base_price.is_zero() && *growth > 0 && !step.is_zero(),
+    };
+    if !valid {
+        return Err(StdError::generic_err(
+            "The base price, the rise and the step of a curve must be greater than 0",
+        ));
+    }
+
+    Ok(())
+}
+
+// Food is minted in its smallest unit, so the whole tokens of the rate are scaled by the decimals
+// of the token. Returns the food the uscrt buy, rounded down, and what that food costs, rounded up
+fn food_for_uscrt(rate: &ExchangeRate, decimals: u8, uscrt: u128) -> StdResult<(u128, u128)> {
+    let overflow = || StdError::generic_err("The amount of food to mint is too large");
+    let food_per_rate = 10u128
+        .checked_pow(decimals as u32)
+        .and_then(|unit| unit.checked_mul(rate.food.u128()))
+        .ok_or_else(overflow)?;
+
+    let food = uscrt.checked_mul(food_per_rate).ok_or_else(overflow)? / rate.uscrt.u128();
+    // food * rate.uscrt is at most uscrt * food_per_rate, which didn't overflow
+    let cost = (food * rate.uscrt.u128())
+        .checked_add(food_per_rate - 1)
+        .ok_or_else(overflow)?
+        / food_per_rate;
+
+    Ok((food, cost))
+}
+
+fn check_exchange_rate(rate: &ExchangeRate) -> StdResult<()> {
+    if rate.food.is_zero() || rate.uscrt.is_zero() {
+        return Err(StdError::generic_err(
+            "Both sides of the exchange rate must be greater than 0",
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn try_withdraw<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    amount: Uint128,
+    recipient: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    // the claimable balances of the payees stay in the contract until they are claimed
+    let available = unclaimed_balance(deps, &env, &state)?;
+    if amount.is_zero() || amount.u128() > available {
+        return Err(StdError::generic_err(format!(
+            "Can't withdraw {} uscrt, the contract holds {} uscrt that isn't claimable",
+            amount, available
+        )));
+    }
+
+    state.total_withdrawn += amount;
+    config(&mut deps.storage).save(&state)?;
+
+    let send_msg = send_uscrt(&env, &recipient, amount);
+
+    Ok(HandleResponse {
+        messages: vec![send_msg],
+        log: vec![
+            log("action", "withdraw"),
+            log("amount", amount),
+            log("recipient", recipient),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_set_payees<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    payees: Vec<Payee>,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    let total_shares: u128 = payees.iter().map(|p| p.share as u128).sum();
+    if total_shares != TOTAL_SHARES {
+        return Err(StdError::generic_err(format!(
+            "The shares of the payees add up to {} basis points instead of {}",
+            total_shares, TOTAL_SHARES
+        )));
+    }
+    for (i, payee) in payees.iter().enumerate() {
+        if payees[..i].iter().any(|p| p.address == payee.address) {
+            return Err(StdError::generic_err(format!(
+                "{} is listed more than once",
+                payee.address
+            )));
+        }
+    }
+
+    state.payees = payees;
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "set_payees")],
+        data: None,
+    })
+}
+
+// Every payee is credited its share of the uscrt that isn't claimable yet, rounded down.
+// The rounding dust stays in the contract for the next distribution
+pub fn try_distribute<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    if state.payees.is_empty() {
+        return Err(StdError::generic_err("No payees to distribute to"));
+    }
+    let amount = unclaimed_balance(deps, &env, &state)?;
+
+    let mut distributed = 0;
+    for payee in state.payees.iter() {
+        let share = payee.share as u128;
+        // split up so the multiplication can't overflow
+        let payout = amount / TOTAL_SHARES * share + amount % TOTAL_SHARES * share / TOTAL_SHARES;
+        let claimable = read_claimable(&deps.storage, &payee.address)?;
+        write_claimable(
+            &mut deps.storage,
+            &payee.address,
+            Uint128(claimable.u128() + payout),
+        )?;
+        distributed += payout;
+    }
+    if distributed == 0 {
+        return Err(StdError::generic_err("Nothing to distribute"));
+    }
+
+    state.total_claimable += Uint128(distributed);
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "distribute"), log("amount", distributed)],
+        data: None,
+    })
+}
+
+pub fn try_claim<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    let payee = env.message.sender.clone();
+    let amount = read_claimable(&deps.storage, &payee)?;
+    if amount.is_zero() {
+        return Err(StdError::generic_err("Nothing to claim"));
+    }
+
+    write_claimable(&mut deps.storage, &payee, Uint128(0))?;
+    state.total_claimable = Uint128(state.total_claimable.u128() - amount.u128());
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![send_uscrt(&env, &payee, amount)],
+        log: vec![
+            log("action", "claim"),
+            log("amount", amount),
+            log("recipient", payee),
+        ],
+        data: None,
+    })
+}
+
+// uscrt held by the contract that isn't claimable by the payees
+fn unclaimed_balance<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: &Env,
+    state: &State,
+) -> StdResult<u128> {
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address.clone(), "uscrt")?;
+    Ok(balance
+        .amount
+        .u128()
+        .saturating_sub(state.total_claimable.u128()))
+}
+
+fn send_uscrt(env: &Env, recipient: &HumanAddr, amount: Uint128) -> CosmosMsg {
+    CosmosMsg::Bank(BankMsg::Send {
+        from_address: env.contract.address.clone(),
+        to_address: recipient.clone(),
+        amount: vec![Coin {
+            denom: "uscrt".to_string(),
+            amount,
+        }],
+    })
+}
+
+pub fn try_set_exchange_rate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    exchange_rate: ExchangeRate,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+    check_exchange_rate(&exchange_rate)?;
+
+    state.exchange_rate = exchange_rate.clone();
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_exchange_rate"),
+            log("food", exchange_rate.food),
+            log("uscrt", exchange_rate.uscrt),
+        ],
+        data: None,
+    })
+}
+
+// the decimals of the new token are read again, so the exchange rate keeps pricing whole tokens
+pub fn try_set_token_contract<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    address: HumanAddr,
+    hash: String,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    let token_info = snip20::token_info_query(
+        &deps.querier,
+        RESPONSE_BLOCK_SIZE,
+        hash.clone(),
+        address.clone(),
+    )?;
+    state.contract_adress = address.clone();
+    state.contract_hash = hash;
+    state.token_decimals = token_info.decimals;
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_token_contract"),
+            log("address", address),
+            log("decimals", token_info.decimals),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_change_admin<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    address: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    state.admin = address.clone();
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", "change_admin"), log("admin", address)],
+        data: None,
+    })
+}
+
+pub fn try_set_pricing<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pricing: Pricing,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+    check_pricing(&pricing)?;
+
+    let mode = match pricing {
+        Pricing::Flat {} => "flat",
+        Pricing::Linear { .. } => "linear",
+        Pricing::Exponential { .. } => "exponential",
+    };
+    state.step_price = base_price(&pricing);
+    state.step_filled = Uint128(0);
+    state.pricing = pricing;
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![
+            log("action", "set_pricing"),
+            log("mode", mode),
+            log("step_price", state.step_price),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_set_paused<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    paused: bool,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    state.paused = paused;
+    config(&mut deps.storage).save(&state)?;
+
+    let action = if paused { "pause" } else { "unpause" };
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("action", action)],
+        data: None,
+    })
+}
+
+fn check_if_admin(state: &State, account: &HumanAddr) -> StdResult<()> {
+    if &state.admin != account {
+        return Err(StdError::generic_err(
+            "This is an admin command. Admin commands can only be run from admin address",
+        ));
+    }
+
+    Ok(())
+}
+
 /* TESTS --------------------------------------------------------------------------------------------------------------------------------------*/
 
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage};
-    use cosmwasm_std::{coins, Extern, InitResponse, StdResult, Uint128};
+    use cosmwasm_std::{
+        coins, from_slice, to_binary, BankMsg, CosmosMsg, Empty, Extern, HumanAddr, InitResponse,
+        Querier, QuerierResult, QueryRequest, StdResult, Uint128, WasmQuery,
+    };
+    use serde::Serialize;
+
+    use crate::msg::{HandleMessage, InitMsg, QueryMessage};
+    use crate::state::{config_read, ExchangeRate, Payee, Pricing};
+
+    use super::{
+        handle, init, query, query_claimable, query_config, query_quote_buy, query_total_minted,
+        query_total_withdrawn,
+    };
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "snake_case")]
+    enum TokenInfoAnswer {
+        TokenInfo {
+            name: String,
+            symbol: String,
+            decimals: u8,
+            total_supply: Option<Uint128>,
+        },
+    }
+
+    // answers the token info query of the food contract, the rest goes to the MockQuerier
+    struct FoodQuerier {
+        decimals: u8,
+        balances: MockQuerier,
+    }
+
+    impl Querier for FoodQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            match from_slice::<QueryRequest<Empty>>(bin_request) {
+                Ok(QueryRequest::Wasm(WasmQuery::Smart { .. })) => {
+                    Ok(to_binary(&TokenInfoAnswer::TokenInfo {
+                        name: "Food".to_string(),
+                        symbol: "FDT".to_string(),
+                        decimals: self.decimals,
+                        total_supply: None,
+                    }))
+                }
+                _ => self.balances.raw_query(bin_request),
+            }
+        }
+    }
 
-    use crate::msg::{HandleMessage, InitMsg};
+    fn mock_deps(decimals: u8) -> Extern<MockStorage, MockApi, FoodQuerier> {
+        let deps = mock_dependencies(20, &[]);
+        Extern {
+            storage: deps.storage,
+            api: deps.api,
+            querier: FoodQuerier {
+                decimals,
+                balances: deps.querier,
+            },
+        }
+    }
 
-    use super::{handle, init};
+    fn rate(food: u128, uscrt: u128) -> ExchangeRate {
+        ExchangeRate {
+            food: Uint128(food),
+            uscrt: Uint128(uscrt),
+        }
+    }
 
     fn init_default() -> (
         StdResult<InitResponse>,
-        Extern<MockStorage, MockApi, MockQuerier>,
+        Extern<MockStorage, MockApi, FoodQuerier>,
     ) {
-        let mut deps = mock_dependencies(20, &[]);
+        let mut deps = mock_deps(2);
         let token = mock_env("snip", &[]);
         let env = mock_env("instantiator", &[]);
 
         let init_msg = InitMsg {
-            token_exchange_rate: Uint128(100),
+            token_exchange_rate: rate(1, 1),
             token_contract_address: token.contract.address,
             token_contract_hash: token.contract_code_hash,
+            pricing: None,
             admin: None,
         };
         (init(&mut deps, env.clone(), init_msg), deps)
@@ -152,37 +761,357 @@ mod tests {
     }
     #[test]
     fn test_buy_with_coins() {
-        let mut deps = mock_dependencies(20, &[]);
+        let mut deps = mock_deps(2);
         let token = mock_env("snip", &[]);
         let env = mock_env("instantiator", &coins(1, "uscrt"));
 
         let init_msg = InitMsg {
-            token_exchange_rate: Uint128(100),
+            token_exchange_rate: rate(1, 1),
             token_contract_address: token.contract.address.clone(),
             token_contract_hash: token.contract_code_hash.clone(),
+            pricing: None,
             admin: None,
         };
         let _res = init(&mut deps, env.clone(), init_msg.clone()).unwrap();
 
-        let msg = HandleMessage::BuyFood {};
+        let msg = HandleMessage::BuyFood {
+            min_amount_out: None,
+        };
         let _res = handle(&mut deps, env.clone(), msg).unwrap();
     }
     #[test]
     fn test_buy_no_coins() {
-        let mut deps = mock_dependencies(20, &[]);
+        let mut deps = mock_deps(2);
         let token = mock_env("snip", &[]);
         let env = mock_env("instantiator", &[]);
 
         let init_msg = InitMsg {
-            token_exchange_rate: Uint128(100),
+            token_exchange_rate: rate(1, 1),
             token_contract_address: token.contract.address,
             token_contract_hash: token.contract_code_hash,
+            pricing: None,
             admin: None,
         };
         let _res = init(&mut deps, env.clone(), init_msg).unwrap();
 
-        let msg = HandleMessage::BuyFood {};
+        let msg = HandleMessage::BuyFood {
+            min_amount_out: None,
+        };
         let res = handle(&mut deps, env, msg);
         assert!(res.is_err(), "should error");
     }
+    #[test]
+    fn test_withdraw() {
+        let (_init_result, mut deps) = init_default();
+        deps.querier
+            .balances
+            .update_balance("cosmos2contract", coins(100, "uscrt"));
+        let withdraw = |amount: u128| HandleMessage::Withdraw {
+            amount: Uint128(amount),
+            recipient: HumanAddr::from("treasury"),
+        };
+
+        let res = handle(&mut deps, mock_env("someone", &[]), withdraw(60));
+        assert!(res.is_err(), "only the admin can withdraw");
+        let res = handle(&mut deps, mock_env("instantiator", &[]), withdraw(150));
+        assert!(res.is_err(), "can't withdraw more than the balance");
+
+        let res = handle(&mut deps, mock_env("instantiator", &[]), withdraw(60)).unwrap();
+        match &res.messages[0] {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address, amount, ..
+            }) => {
+                assert_eq!(to_address, &HumanAddr::from("treasury"));
+                assert_eq!(amount, &coins(60, "uscrt"));
+            }
+            _ => panic!("expected a bank send"),
+        }
+        assert_eq!(query_total_withdrawn(&deps).unwrap().amount, Uint128(60));
+        assert!(query(&deps, QueryMessage::TotalWithdrawn {}).is_ok());
+    }
+    #[test]
+    fn test_distribute() {
+        let (_init_result, mut deps) = init_default();
+        deps.querier
+            .balances
+            .update_balance("cosmos2contract", coins(1001, "uscrt"));
+        let admin = mock_env("instantiator", &[]);
+        let payee = |address: &str, share: u16| Payee {
+            address: HumanAddr::from(address),
+            share,
+        };
+
+        let res = handle(&mut deps, admin.clone(), HandleMessage::Distribute {});
+        assert!(res.is_err(), "there are no payees yet");
+        let msg = HandleMessage::SetPayees {
+            payees: vec![payee("treasury", 6000), payee("artists", 3000)],
+        };
+        let res = handle(&mut deps, admin.clone(), msg);
+        assert!(res.is_err(), "the shares have to add up to 100%");
+        let msg = HandleMessage::SetPayees {
+            payees: vec![payee("treasury", 6000), payee("artists", 4000)],
+        };
+        handle(&mut deps, admin.clone(), msg).unwrap();
+
+        handle(
+            &mut deps,
+            mock_env("anyone", &[]),
+            HandleMessage::Distribute {},
+        )
+        .unwrap();
+        let claimable = |deps: &Extern<MockStorage, MockApi, FoodQuerier>, address: &str| {
+            query_claimable(deps, HumanAddr::from(address))
+                .unwrap()
+                .amount
+        };
+        assert_eq!(claimable(&deps, "treasury"), Uint128(600));
+        assert_eq!(claimable(&deps, "artists"), Uint128(400));
+
+        let withdraw = HandleMessage::Withdraw {
+            amount: Uint128(2),
+            recipient: HumanAddr::from("instantiator"),
+        };
+        let res = handle(&mut deps, admin.clone(), withdraw);
+        assert!(res.is_err(), "only the rounding dust isn't claimable");
+
+        let res = handle(&mut deps, mock_env("artists", &[]), HandleMessage::Claim {}).unwrap();
+        match &res.messages[0] {
+            CosmosMsg::Bank(BankMsg::Send { amount, .. }) => {
+                assert_eq!(amount, &coins(400, "uscrt"))
+            }
+            _ => panic!("expected a bank send"),
+        }
+        assert_eq!(claimable(&deps, "artists"), Uint128(0));
+        let res = handle(&mut deps, mock_env("artists", &[]), HandleMessage::Claim {});
+        assert!(res.is_err(), "nothing left to claim");
+    }
+    #[test]
+    fn test_exchange_rate() {
+        let mut deps = mock_deps(0);
+        let env = mock_env("instantiator", &[]);
+        let init_msg = |exchange_rate: ExchangeRate| InitMsg {
+            token_exchange_rate: exchange_rate,
+            token_contract_address: HumanAddr::from("snip"),
+            token_contract_hash: "".to_string(),
+            pricing: None,
+            admin: None,
+        };
+        let res = init(&mut deps, env.clone(), init_msg(rate(0, 3)));
+        assert!(res.is_err(), "the rate needs both sides");
+        init(&mut deps, env, init_msg(rate(1, 3))).unwrap();
+
+        // 10 uscrt buy 3 FDT for 9 uscrt, the last uscrt is refunded
+        let env = mock_env("buyer", &coins(10, "uscrt"));
+        let res = handle(
+            &mut deps,
+            env,
+            HandleMessage::BuyFood {
+                min_amount_out: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert!(res
+            .log
+            .iter()
+            .any(|l| l.key == "food_amount" && l.value == "3"));
+        match &res.messages[1] {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address, amount, ..
+            }) => {
+                assert_eq!(to_address, &HumanAddr::from("buyer"));
+                assert_eq!(amount, &coins(1, "uscrt"));
+            }
+            _ => panic!("expected a refund"),
+        }
+        let state = config_read(&deps.storage).load().unwrap();
+        assert_eq!(state.total_raised, Uint128(9));
+
+        let env = mock_env("buyer", &coins(2, "uscrt"));
+        let res = handle(
+            &mut deps,
+            env,
+            HandleMessage::BuyFood {
+                min_amount_out: None,
+            },
+        );
+        assert!(res.is_err(), "2 uscrt don't buy a whole FDT");
+
+        let env = mock_env("buyer", &coins(u128::MAX, "uscrt"));
+        let mut deps = mock_deps(18);
+        init(&mut deps, env.clone(), init_msg(rate(1, 3))).unwrap();
+        let res = handle(
+            &mut deps,
+            env,
+            HandleMessage::BuyFood {
+                min_amount_out: None,
+            },
+        );
+        assert!(res.is_err(), "the amount to mint overflows");
+    }
+    #[test]
+    fn test_admin_config() {
+        let (_init_result, mut deps) = init_default();
+        let admin = mock_env("instantiator", &[]);
+        let buy = mock_env("buyer", &coins(3, "uscrt"));
+
+        let msg = HandleMessage::SetExchangeRate {
+            exchange_rate: rate(1, 3),
+        };
+        let res = handle(&mut deps, mock_env("someone", &[]), msg.clone());
+        assert!(res.is_err(), "only the admin can change the rate");
+        let res = handle(&mut deps, admin.clone(), msg).unwrap();
+        assert!(res.log.iter().any(|l| l.key == "uscrt" && l.value == "3"));
+        let res = handle(
+            &mut deps,
+            buy.clone(),
+            HandleMessage::BuyFood {
+                min_amount_out: None,
+            },
+        )
+        .unwrap();
+        assert!(res
+            .log
+            .iter()
+            .any(|l| l.key == "food_amount" && l.value == "100"));
+
+        deps.querier.decimals = 6;
+        let msg = HandleMessage::SetTokenContract {
+            address: HumanAddr::from("new food"),
+            hash: "new hash".to_string(),
+        };
+        handle(&mut deps, admin.clone(), msg).unwrap();
+        let state = config_read(&deps.storage).load().unwrap();
+        assert_eq!(state.contract_adress, HumanAddr::from("new food"));
+        assert_eq!(state.token_decimals, 6);
+
+        handle(&mut deps, admin.clone(), HandleMessage::Pause {}).unwrap();
+        let res = handle(
+            &mut deps,
+            buy.clone(),
+            HandleMessage::BuyFood {
+                min_amount_out: None,
+            },
+        );
+        assert!(res.is_err(), "food can't be bought while paused");
+        handle(&mut deps, admin.clone(), HandleMessage::Unpause {}).unwrap();
+        handle(
+            &mut deps,
+            buy,
+            HandleMessage::BuyFood {
+                min_amount_out: None,
+            },
+        )
+        .unwrap();
+
+        let msg = HandleMessage::ChangeAdmin {
+            address: HumanAddr::from("new admin"),
+        };
+        handle(&mut deps, admin.clone(), msg).unwrap();
+        let res = handle(&mut deps, admin, HandleMessage::Pause {});
+        assert!(res.is_err(), "the old admin lost its rights");
+        handle(
+            &mut deps,
+            mock_env("new admin", &[]),
+            HandleMessage::Pause {},
+        )
+        .unwrap();
+    }
+    #[test]
+    fn test_bonding_curve() {
+        let (_init_result, mut deps) = init_default();
+        let admin = mock_env("instantiator", &[]);
+        let quote =
+            |deps: &Extern<_, _, _>, uscrt: u128| query_quote_buy(deps, Uint128(uscrt)).unwrap();
+        let buy = |uscrt: u128, min_amount_out: Option<u128>| {
+            (
+                mock_env("buyer", &coins(uscrt, "uscrt")),
+                HandleMessage::BuyFood {
+                    min_amount_out: min_amount_out.map(Uint128),
+                },
+            )
+        };
+
+        let msg = HandleMessage::SetPricing {
+            pricing: Pricing::Linear {
+                base_price: Uint128(10),
+                increase: Uint128(0),
+                step: Uint128(100),
+            },
+        };
+        let res = handle(&mut deps, admin.clone(), msg);
+        assert!(res.is_err(), "a curve has to rise");
+
+        // a whole food costs 10 uscrt at first and 10 more after every whole food minted
+        let msg = HandleMessage::SetPricing {
+            pricing: Pricing::Linear {
+                base_price: Uint128(10),
+                increase: Uint128(10),
+                step: Uint128(100),
+            },
+        };
+        handle(&mut deps, admin.clone(), msg).unwrap();
+
+        let res = quote(&deps, 25);
+        assert_eq!((res.food_amount, res.cost), (Uint128(175), Uint128(25)));
+        let (env, msg) = buy(25, Some(176));
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err(), "less food than the minimum");
+        let (env, msg) = buy(25, Some(175));
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert!(res
+            .log
+            .iter()
+            .any(|l| l.key == "food_amount" && l.value == "175"));
+
+        // the rest of the second step costs 20 uscrt per whole food, then the price is 30
+        let (env, msg) = buy(5, None);
+        let res = handle(&mut deps, env, msg).unwrap();
+        assert!(res
+            .log
+            .iter()
+            .any(|l| l.key == "food_amount" && l.value == "25"));
+        assert!(res.log.iter().any(|l| l.key == "refund" && l.value == "0"));
+        assert_eq!(query_total_minted(&deps).unwrap().amount, Uint128(200));
+        let config = query_config(&deps).unwrap();
+        assert_eq!(config.step_price, Uint128(30));
+
+        // every step is 50% more expensive
+        let msg = HandleMessage::SetPricing {
+            pricing: Pricing::Exponential {
+                base_price: Uint128(100),
+                growth: 5000,
+                step: Uint128(100),
+            },
+        };
+        handle(&mut deps, admin.clone(), msg).unwrap();
+        let res = quote(&deps, 250);
+        assert_eq!((res.food_amount, res.cost), (Uint128(200), Uint128(250)));
+        let res = quote(&deps, 475);
+        assert_eq!(res.food_amount, Uint128(300));
+
+        // every step holds the smallest unit of food
+        let msg = HandleMessage::SetPricing {
+            pricing: Pricing::Linear {
+                base_price: Uint128(1),
+                increase: Uint128(1),
+                step: Uint128(1),
+            },
+        };
+        handle(&mut deps, admin.clone(), msg).unwrap();
+        let res = query_quote_buy(&deps, Uint128(10000));
+        assert!(res.is_err(), "too many price steps at once");
+
+        handle(
+            &mut deps,
+            admin,
+            HandleMessage::SetPricing {
+                pricing: Pricing::Flat {},
+            },
+        )
+        .unwrap();
+        let res = quote(&deps, 3);
+        assert_eq!((res.food_amount, res.refund), (Uint128(300), Uint128(0)));
+        assert_eq!(query_total_minted(&deps).unwrap().amount, Uint128(200));
+    }
 }
@@ -1,7 +1,7 @@
+mod constants;
 pub mod contract;
 pub mod msg;
 pub mod state;
-mod constants;
 
 #[cfg(target_arch = "wasm32")]
 mod wasm {
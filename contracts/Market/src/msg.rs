@@ -2,19 +2,57 @@ use cosmwasm_std::{HumanAddr, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::state::{ExchangeRate, Payee, Pricing};
+
 #[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Debug)]
 #[serde(rename_all = "snake_case")]
 pub struct InitMsg {
     pub token_contract_address: HumanAddr,
     pub token_contract_hash: String,
-    pub token_exchange_rate: Uint128,
+    pub token_exchange_rate: ExchangeRate,
+    // flat pricing at the exchange rate by default
+    pub pricing: Option<Pricing>,
     pub admin: Option<HumanAddr>,
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum HandleMessage {
-    BuyFood {},
+    // fails if less than min_amount_out food would be minted
+    BuyFood {
+        min_amount_out: Option<Uint128>,
+    },
+    Withdraw {
+        amount: Uint128,
+        recipient: HumanAddr,
+    },
+    // admin only, the shares have to add up to 10000 basis points
+    SetPayees {
+        payees: Vec<Payee>,
+    },
+    // anyone, splits the uscrt that isn't claimable yet between the payees
+    Distribute {},
+    // sends the claimable balance of the sender
+    Claim {},
+    // admin only
+    SetExchangeRate {
+        exchange_rate: ExchangeRate,
+    },
+    // admin only, the Market has to be a minter of the new token
+    SetTokenContract {
+        address: HumanAddr,
+        hash: String,
+    },
+    ChangeAdmin {
+        address: HumanAddr,
+    },
+    // admin only, a curve starts again at its base price
+    SetPricing {
+        pricing: Pricing,
+    },
+    // admin only, stops and resumes the sale of food
+    Pause {},
+    Unpause {},
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Debug)]
@@ -22,6 +60,12 @@ pub enum HandleMessage {
 pub enum QueryMessage {
     Config {},
     TotalRaised {},
+    TotalWithdrawn {},
+    Payees {},
+    Claimable { address: HumanAddr },
+    TotalMinted {},
+    // the food the uscrt would buy right now
+    QuoteBuy { uscrt_amount: Uint128 },
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
@@ -29,7 +73,11 @@ pub struct ConfigResponse {
     pub token_contract_hash: String,
     pub token_contract_address: HumanAddr,
     pub admin: HumanAddr,
-    pub exchange_rate: Uint128,
+    pub exchange_rate: ExchangeRate,
+    pub token_decimals: u8,
+    pub paused: bool,
+    pub pricing: Pricing,
+    pub step_price: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
@@ -37,3 +85,30 @@ pub struct ConfigResponse {
 pub struct TotalRaisedResponse {
     pub amount: Uint128,
 }
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct TotalWithdrawnResponse {
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct PayeesResponse {
+    pub payees: Vec<Payee>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct ClaimableResponse {
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct TotalMintedResponse {
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct QuoteBuyResponse {
+    pub food_amount: Uint128,
+    pub cost: Uint128,
+    pub refund: Uint128,
+}
@@ -1,17 +1,78 @@
-use cosmwasm_std::{HumanAddr, Storage, Uint128};
-use cosmwasm_storage::{singleton, singleton_read, ReadonlySingleton, Singleton};
+use cosmwasm_std::{HumanAddr, ReadonlyStorage, StdResult, Storage, Uint128};
+use cosmwasm_storage::{
+    singleton, singleton_read, PrefixedStorage, ReadonlyPrefixedStorage, ReadonlySingleton,
+    Singleton,
+};
 use schemars::JsonSchema;
+use secret_toolkit::storage::{TypedStore, TypedStoreMut};
 use serde::{Deserialize, Serialize};
 
 pub static CONFIG_KEY: &[u8] = b"config";
+pub const PREFIX_CLAIMABLE: &[u8] = b"claimable";
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, JsonSchema)]
 pub struct State {
-    pub exchange_rate: Uint128,
+    pub exchange_rate: ExchangeRate,
+    // decimals of the food token, read from its token info
+    pub token_decimals: u8,
     pub admin: HumanAddr,
     pub contract_adress: HumanAddr,
     pub contract_hash: String,
     pub total_raised: Uint128,
+    pub total_withdrawn: Uint128,
+    // the proceeds are split between the payees when distributed
+    pub payees: Vec<Payee>,
+    // distributed but not yet claimed, the admin can't withdraw it
+    pub total_claimable: Uint128,
+    // food can't be bought while paused
+    pub paused: bool,
+    pub pricing: Pricing,
+    pub total_minted: Uint128,
+    // price of a whole food token in the current step of the curve
+    pub step_price: Uint128,
+    // food minted in the current step of the curve
+    pub step_filled: Uint128,
+}
+
+// `uscrt` uscrt buy `food` whole food tokens, so 1 FDT for 3 uscrt is food 1 and uscrt 3
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, JsonSchema)]
+pub struct ExchangeRate {
+    pub food: Uint128,
+    pub uscrt: Uint128,
+}
+
+// Prices of the curves are in uscrt for a whole food token, steps in the smallest unit of food.
+// A curve starts at its base price when it's set and the price rises every step of food minted
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Pricing {
+    // food is sold at the exchange rate
+    Flat {},
+    // the price rises by `increase` uscrt every step
+    Linear {
+        base_price: Uint128,
+        increase: Uint128,
+        step: Uint128,
+    },
+    // the price rises by `growth` basis points every step, rounded up so it always rises
+    Exponential {
+        base_price: Uint128,
+        growth: u16,
+        step: Uint128,
+    },
+}
+
+impl Default for Pricing {
+    fn default() -> Self {
+        Pricing::Flat {}
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, JsonSchema)]
+pub struct Payee {
+    pub address: HumanAddr,
+    // in basis points, 100 is 1%
+    pub share: u16,
 }
 
 // returns a mutable singleton instance of the storage
@@ -22,3 +83,23 @@ pub fn config<S: Storage>(storage: &mut S) -> Singleton<S, State> {
 pub fn config_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, State> {
     singleton_read(storage, CONFIG_KEY)
 }
+
+// Claimable balances of the payees
+
+pub fn read_claimable<S: ReadonlyStorage>(storage: &S, payee: &HumanAddr) -> StdResult<Uint128> {
+    let claimable_store = ReadonlyPrefixedStorage::new(PREFIX_CLAIMABLE, storage);
+    let claimable_store = TypedStore::attach(&claimable_store);
+    Ok(claimable_store
+        .may_load(payee.as_str().as_bytes())?
+        .unwrap_or_default())
+}
+
+pub fn write_claimable<S: Storage>(
+    storage: &mut S,
+    payee: &HumanAddr,
+    amount: Uint128,
+) -> StdResult<()> {
+    let mut claimable_store = PrefixedStorage::new(PREFIX_CLAIMABLE, storage);
+    let mut claimable_store = TypedStoreMut::attach(&mut claimable_store);
+    claimable_store.store(payee.as_str().as_bytes(), &amount)
+}
@@ -3,7 +3,7 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use pet::msg::{HandleMsg, InitMsg, QueryMsg, QueryResponse};
+use pet::msg::{HandleAnswer, HandleMsg, InitMsg, NftQueryAnswer, QueryMsg, QueryResponse};
 use pet::state::State;
 
 fn main() {
@@ -14,7 +14,9 @@ fn main() {
 
     export_schema(&schema_for!(InitMsg), &out_dir);
     export_schema(&schema_for!(HandleMsg), &out_dir);
+    export_schema(&schema_for!(HandleAnswer), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(State), &out_dir);
     export_schema(&schema_for!(QueryResponse), &out_dir);
+    export_schema(&schema_for!(NftQueryAnswer), &out_dir);
 }
@@ -1 +1,43 @@
+use crate::state::StatDecay;
+
 pub static RESPONSE_BLOCK_SIZE: usize = 256;
+// hunger percentage from which a pet that can be fed is considered starving
+pub static STARVING_HUNGER_PERCENTAGE: u64 = 75;
+// age in seconds at which pets reach each stage when no stages are configured
+pub static DEFAULT_STAGE_AGES: [u64; 5] = [0, 3600, 86400, 259200, 1209600];
+// longest time in seconds a pet can sleep when no sleep config is set, a week
+pub static DEFAULT_MAX_SLEEP_DURATION: u64 = 604800;
+// seconds after waking up before a pet can sleep again when no sleep config is set
+pub static DEFAULT_SLEEP_COOLDOWN: u64 = 604800;
+// highest value of the happiness, health, hygiene and energy stats
+pub static MAX_STAT_VALUE: u64 = 100;
+// health below which a pet is considered sick
+pub static SICK_HEALTH_VALUE: u64 = 30;
+// points lost per hour when no stat decay is configured
+pub static DEFAULT_STAT_DECAY: StatDecay = StatDecay {
+    happiness: 4,
+    health: 1,
+    hygiene: 3,
+    energy: 5,
+};
+// highest nutrition of a food token, a unit of it saturates ten times as much as the accepted token
+pub static MAX_FOOD_NUTRITION: u64 = 1000;
+// on-time feedings in a row need to span this many seconds for the week streak achievement
+pub static ACHIEVEMENT_STREAK_DURATION: u64 = 604800;
+// care score an owner gets for feeding a pet on time, or later but without overfeeding
+pub static CARE_SCORE_ON_TIME_FEEDING: u64 = 10;
+pub static CARE_SCORE_LATE_FEEDING: u64 = 2;
+// care score an owner loses when the death of one of their pets is recorded
+pub static CARE_SCORE_DEATH_PENALTY: u64 = 50;
+// seconds after breeding before a parent can breed again when no breeding config is set
+pub static DEFAULT_BREEDING_COOLDOWN: u64 = 259200;
+// number of owners kept in the best caretakers leaderboard
+pub static CARETAKER_LEADERBOARD_SIZE: usize = 100;
+// the leaderboard rounds scores and streaks down to these steps, so it doesn't move on every feeding
+pub static LEADERBOARD_CARE_SCORE_STEP: u64 = 100;
+pub static LEADERBOARD_STREAK_STEP: u32 = 10;
+// name and symbol of the pet tokens in the SNIP-721 contract info
+pub static NFT_NAME: &str = "Tamagotchi";
+pub static NFT_SYMBOL: &str = "PET";
+// number of token ids returned by the token list queries when no limit is given
+pub static DEFAULT_TOKEN_LIST_LIMIT: u32 = 30;
@@ -1,52 +1,132 @@
 use std::vec;
 
 use cosmwasm_std::{
-    log, to_binary, Api, Binary, Env, Extern, HandleResponse, HumanAddr, InitResponse, Querier,
-    QueryResult, StdError, StdResult, Storage, Uint128,
+    from_binary, log, to_binary, Api, Binary, BlockInfo, CosmosMsg, Env, Extern, HandleResponse,
+    HumanAddr, InitResponse, Querier, QueryResult, StdError, StdResult, Storage, Uint128,
 };
 
 use crate::{
-    constants::RESPONSE_BLOCK_SIZE,
-    msg::{HandleMsg, InitMsg, QueryMsg, QueryResponse},
-    state::{config, config_read, Pet, State},
+    constants::{
+        CARE_SCORE_DEATH_PENALTY, CARE_SCORE_LATE_FEEDING, CARE_SCORE_ON_TIME_FEEDING,
+        DEFAULT_BREEDING_COOLDOWN, DEFAULT_MAX_SLEEP_DURATION, DEFAULT_SLEEP_COOLDOWN,
+        DEFAULT_STAT_DECAY, DEFAULT_TOKEN_LIST_LIMIT, MAX_FOOD_NUTRITION, NFT_NAME, NFT_SYMBOL,
+        RESPONSE_BLOCK_SIZE,
+    },
+    leaderboard::{
+        get_ranked_pets, is_death_passed, read_caretaker_ranking, set_pet_ranked,
+        start_death_hours, unrank_dead_pets,
+    },
+    msg::{
+        AccessLevel, ContractStatusLevel, Extension, FoodTokenInfo, HandleAnswer, HandleMsg,
+        InitMsg, LeaderboardPet, NftQueryAnswer, QueryMsg, QueryResponse, QueryWithPermit,
+        ReceiveMsg, ResponseStatus::Success, Send, Trait, Transfer,
+    },
+    pet_history::{get_graves, get_pet_history, store_grave, store_pet_event, PetEvent},
+    receiver::Snip721ReceiveMsg,
+    state::{
+        add_owned_pet, config, config_read, default_stages, load_owner_record, load_pet,
+        read_nft_receiver, read_operators, read_owned_pets, read_pet_name, read_viewing_key,
+        remove_owned_pet, remove_pet_name, save_owner_record, save_pet, write_nft_receiver,
+        write_operators, write_pet_name, write_viewing_key, Achievement, Approval, BreedingPartner,
+        CareAction, Caretaker, Expiration, FeedingGame, FoodToken, NftReceiver, Pet, PetMetadata,
+        SecretToken, Stage, StageConfig, StatDecay, State, Stats,
+    },
+};
+use common::{
+    rand::{sha_256, Prng},
+    viewing_key::{ViewingKey, VIEWING_KEY_SIZE},
 };
+use secret_toolkit::permit::{validate, Permission, Permit, RevokedPermits};
 use secret_toolkit::snip20;
 
+pub const PREFIX_REVOKED_PERMITS: &str = "revoked_permits";
+
 pub fn init<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     msg: InitMsg,
 ) -> StdResult<InitResponse> {
+    if msg.saturation_per_food == 0 {
+        return Err(StdError::generic_err(
+            "Saturation per food must be greater than 0",
+        ));
+    }
+
+    let stages = match msg.stages {
+        Some(stages) => stages,
+        None => default_stages(msg.allowed_feed_timespan, msg.total_saturation_time),
+    };
+    check_stages(&stages)?;
+
     let state = State {
         accepted_token: msg.accepted_token.clone(),
-        admin: Some(msg.admin.unwrap_or(env.message.sender)),
-        pet: Pet {
-            last_fed: env.block.time,
-            allowed_feed_timespan: msg.allowed_feed_timespan,
-            total_saturation_time: msg.total_saturation_time,
-        },
+        food_tokens: vec![],
+        admin: Some(msg.admin.unwrap_or_else(|| env.message.sender.clone())),
+        allowed_feed_timespan: msg.allowed_feed_timespan,
+        total_saturation_time: msg.total_saturation_time,
+        stages,
+        saturation_per_food: msg.saturation_per_food,
+        refund_excess_food: msg.refund_excess_food,
+        pet_count: 0,
+        adoption_price: msg.adoption_price,
+        max_pets_per_owner: msg.max_pets_per_owner,
+        stat_decay: msg.stat_decay.unwrap_or_else(|| DEFAULT_STAT_DECAY.clone()),
+        revive_price: msg.revive_price,
+        revive_price_increase: msg.revive_price_increase.unwrap_or_default(),
+        contract_status: ContractStatusLevel::NormalRun,
+        paused_at: None,
+        paused_duration: 0,
+        sleep_price: None,
+        max_sleep_duration: DEFAULT_MAX_SLEEP_DURATION,
+        sleep_cooldown: DEFAULT_SLEEP_COOLDOWN,
+        feeding_game: None,
+        prng_seed: sha_256(&msg.prng_seed.0).to_vec(),
+        contract_address: env.contract.address.clone(),
+        rename_price: None,
+        unique_names: false,
+        breed_price: None,
+        breeding_cooldown: DEFAULT_BREEDING_COOLDOWN,
     };
     config(&mut deps.storage).save(&state)?;
+    start_death_hours(&mut deps.storage, env.block.time)?;
+
+    Ok(InitResponse {
+        messages: register_token_msgs(&env, msg.accepted_token)?,
+        log: vec![],
+    })
+}
 
+// registers the receive hook and the viewing key of the contract with the token
+fn register_token_msgs(env: &Env, token: SecretToken) -> StdResult<Vec<CosmosMsg>> {
     let recieve_msg = snip20::register_receive_msg(
         env.contract_code_hash.clone(),
         None,
         RESPONSE_BLOCK_SIZE,
-        msg.accepted_token.hash.clone(),
-        msg.accepted_token.address.clone(),
+        token.hash.clone(),
+        token.address.clone(),
     )?;
     let view_key_msg = snip20::set_viewing_key_msg(
-        msg.accepted_token.viewing_key,
+        token.viewing_key,
         None,
         RESPONSE_BLOCK_SIZE,
-        msg.accepted_token.hash.clone(),
-        msg.accepted_token.address.clone(),
+        token.hash,
+        token.address,
     )?;
 
-    Ok(InitResponse {
-        messages: vec![recieve_msg, view_key_msg],
-        log: vec![],
-    })
+    Ok(vec![recieve_msg, view_key_msg])
+}
+
+fn check_stages(stages: &[StageConfig]) -> StdResult<()> {
+    if stages.first().map(|s| s.min_age) != Some(0) {
+        return Err(StdError::generic_err("The first stage must start at age 0"));
+    }
+    if stages.windows(2).any(|w| w[0].min_age >= w[1].min_age) {
+        return Err(StdError::generic_err(
+            "Stages must be ordered by increasing min_age",
+        ));
+    }
+
+    Ok(())
 }
 
 pub fn handle<S: Storage, A: Api, Q: Querier>(
@@ -54,39 +134,284 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     env: Env,
     msg: HandleMsg,
 ) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    unrank_dead_pets(&mut deps.storage, state.running_time(env.block.time))?;
+    let contract_status = state.contract_status;
+
+    match contract_status {
+        ContractStatusLevel::StopAll | ContractStatusLevel::StopAllButFeeding => {
+            return match msg {
+                HandleMsg::SetContractStatus { level } => try_set_contract_status(deps, env, level),
+                HandleMsg::Receive {
+                    from, amount, msg, ..
+                } if contract_status == ContractStatusLevel::StopAllButFeeding => {
+                    try_receive(deps, env, from, amount, msg)
+                }
+                HandleMsg::Care { pet_id, action }
+                    if contract_status == ContractStatusLevel::StopAllButFeeding =>
+                {
+                    try_care(deps, env, pet_id, action)
+                }
+                _ => Err(StdError::generic_err(
+                    "This contract is stopped and this action is not allowed",
+                )),
+            };
+        }
+        ContractStatusLevel::NormalRun => {} // If it's a normal run just continue
+    }
+
     match msg {
         HandleMsg::Receive {
             from, amount, msg, ..
-        } => try_feed(deps, env, from, amount, msg),
+        } => try_receive(deps, env, from, amount, msg),
+        HandleMsg::Adopt {
+            name,
+            description,
+            image_uri,
+        } => {
+            let metadata = PetMetadata {
+                name,
+                description,
+                image_uri,
+            };
+            try_adopt(deps, env, metadata)
+        }
+        HandleMsg::Rename {
+            pet_id,
+            name,
+            description,
+            image_uri,
+        } => {
+            let metadata = PetMetadata {
+                name,
+                description,
+                image_uri,
+            };
+            try_rename(deps, env, pet_id, metadata)
+        }
+        HandleMsg::TransferPet { pet_id, recipient } => {
+            try_transfer_pet(deps, env, pet_id, recipient)
+        }
+        HandleMsg::AddCaretaker {
+            pet_id,
+            caretaker,
+            expiration,
+        } => try_add_caretaker(deps, env, pet_id, caretaker, expiration),
+        HandleMsg::RemoveCaretaker { pet_id, caretaker } => {
+            try_remove_caretaker(deps, env, pet_id, caretaker)
+        }
+        HandleMsg::TransferNft {
+            recipient,
+            token_id,
+            ..
+        } => try_transfer_nft(deps, env, token_id, recipient),
+        HandleMsg::SendNft {
+            contract,
+            token_id,
+            msg,
+            ..
+        } => try_send_nft(deps, env, token_id, contract, msg),
+        HandleMsg::Approve {
+            spender,
+            token_id,
+            expires,
+        } => try_approve(deps, env, token_id, spender, expires),
+        HandleMsg::Revoke { spender, token_id } => try_revoke(deps, env, token_id, spender),
+        HandleMsg::ApproveAll { operator, expires } => {
+            try_approve_all(deps, env, operator, expires)
+        }
+        HandleMsg::RevokeAll { operator } => try_revoke_all(deps, env, operator),
+        HandleMsg::SetWhitelistedApproval {
+            address,
+            token_id,
+            view_private_metadata,
+            transfer,
+            expires,
+            ..
+        } => try_set_whitelisted_approval(
+            deps,
+            env,
+            address,
+            token_id,
+            view_private_metadata,
+            transfer,
+            expires,
+        ),
+        HandleMsg::BatchTransferNft { transfers } => try_batch_transfer_nft(deps, env, transfers),
+        HandleMsg::BatchSendNft { sends } => try_batch_send_nft(deps, env, sends),
+        HandleMsg::RegisterReceiveNft {
+            code_hash,
+            also_implements_batch_receive_nft,
+        } => try_register_receive_nft(deps, env, code_hash, also_implements_batch_receive_nft),
+        HandleMsg::SetAdoptionConfig {
+            adoption_price,
+            max_pets_per_owner,
+        } => try_set_adoption_config(deps, env, adoption_price, max_pets_per_owner),
+        HandleMsg::SetReviveConfig {
+            revive_price,
+            revive_price_increase,
+        } => try_set_revive_config(deps, env, revive_price, revive_price_increase),
+        HandleMsg::SetNameConfig {
+            rename_price,
+            unique_names,
+        } => try_set_name_config(deps, env, rename_price, unique_names),
+        HandleMsg::Revive { pet_id } => try_revive(deps, env, pet_id),
+        HandleMsg::Breed {
+            parent_a,
+            parent_b,
+            name,
+        } => try_breed(deps, env, parent_a, parent_b, name),
+        HandleMsg::SetBreedingPartner { pet_id, partner } => {
+            try_set_breeding_partner(deps, env, pet_id, partner)
+        }
+        HandleMsg::SetBreedingConfig {
+            breed_price,
+            breeding_cooldown,
+        } => try_set_breeding_config(deps, env, breed_price, breeding_cooldown),
+        HandleMsg::Bury { pet_id } => try_bury(deps, env, pet_id),
+        HandleMsg::Sleep { pet_id, duration } => try_sleep(deps, env, pet_id, duration),
+        HandleMsg::Wake { pet_id } => try_wake(deps, env, pet_id),
+        HandleMsg::SetSleepConfig {
+            sleep_price,
+            max_sleep_duration,
+            sleep_cooldown,
+        } => try_set_sleep_config(deps, env, sleep_price, max_sleep_duration, sleep_cooldown),
+        HandleMsg::Care { pet_id, action } => try_care(deps, env, pet_id, action),
+        HandleMsg::UpdateConfig {
+            allowed_feed_timespan,
+            total_saturation_time,
+            stages,
+        } => try_update_config(
+            deps,
+            env,
+            allowed_feed_timespan,
+            total_saturation_time,
+            stages,
+        ),
+        HandleMsg::ChangeAdmin { address } => try_change_admin(deps, env, address),
+        HandleMsg::SetAcceptedToken { accepted_token } => {
+            try_set_accepted_token(deps, env, accepted_token)
+        }
+        HandleMsg::SetContractStatus { level } => try_set_contract_status(deps, env, level),
+        HandleMsg::AddFoodToken { food_token } => try_add_food_token(deps, env, food_token),
+        HandleMsg::RemoveFoodToken { address } => try_remove_food_token(deps, env, address),
+        HandleMsg::SetFeedingGame { feeding_game } => try_set_feeding_game(deps, env, feeding_game),
+        HandleMsg::SetLeaderboardOptOut { opt_out } => {
+            try_set_leaderboard_opt_out(deps, env, opt_out)
+        }
+        HandleMsg::CreateViewingKey { entropy } => try_create_key(deps, env, entropy),
+        HandleMsg::SetViewingKey { key } => try_set_key(deps, env, key),
+        HandleMsg::RevokePermit { permit_name } => revoke_permit(deps, env, permit_name),
     }
 }
 
-pub fn try_feed<S: Storage, A: Api, Q: Querier>(
+pub fn try_receive<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
-    _from: HumanAddr,
+    from: HumanAddr,
     amount: Uint128,
-    _msg: Option<Binary>,
+    msg: Option<Binary>,
 ) -> StdResult<HandleResponse> {
-    let mut state = config_read(&deps.storage).load()?;
-    let mut pet: &mut Pet = &mut state.pet;
+    let state = config_read(&deps.storage).load()?;
 
-    if env.message.sender != state.accepted_token.address {
-        return Err(StdError::generic_err(
-            "Only valid Food tokens are accepted. Invalid token sent. ",
-        ));
+    let food = state.food_token(&env.message.sender).ok_or_else(|| {
+        StdError::generic_err("Only valid Food tokens are accepted. Invalid token sent. ")
+    })?;
+
+    let msg = msg.ok_or_else(|| {
+        StdError::generic_err("Missing msg. Specify which pet the food is meant for. ")
+    })?;
+    match from_binary(&msg)? {
+        ReceiveMsg::Feed { pet_id } => try_feed(deps, env, from, amount, pet_id, food),
+        _ if state.contract_status != ContractStatusLevel::NormalRun => Err(StdError::generic_err(
+            "This contract is stopped and this action is not allowed",
+        )),
+        _ if env.message.sender != state.accepted_token.address => Err(StdError::generic_err(
+            "This food can only be eaten. Pay with the accepted token instead. ",
+        )),
+        ReceiveMsg::Adopt {
+            name,
+            description,
+            image_uri,
+        } => {
+            let metadata = PetMetadata {
+                name,
+                description,
+                image_uri,
+            };
+            try_paid_adopt(deps, env, from, amount, metadata)
+        }
+        ReceiveMsg::Rename {
+            pet_id,
+            name,
+            description,
+            image_uri,
+        } => {
+            let metadata = PetMetadata {
+                name,
+                description,
+                image_uri,
+            };
+            try_paid_rename(deps, env, from, amount, pet_id, metadata)
+        }
+        ReceiveMsg::Revive { pet_id } => try_paid_revive(deps, env, from, amount, pet_id),
+        ReceiveMsg::Breed {
+            parent_a,
+            parent_b,
+            name,
+        } => try_paid_breed(deps, env, from, amount, parent_a, parent_b, name),
+        ReceiveMsg::Sleep { pet_id, duration } => {
+            try_paid_sleep(deps, env, from, amount, pet_id, duration)
+        }
     }
-    if pet.is_dead(&env) {
-        return Err(StdError::generic_err(
-            "Pet is already dead :(. You forgot to feed it. ",
-        ));
+}
+
+pub fn try_adopt<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    metadata: PetMetadata,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    if let Some(price) = state.adoption_price {
+        return Err(StdError::generic_err(format!(
+            "Adoption costs {} Food. Send the Food with an adopt msg instead. ",
+            price
+        )));
     }
-    if !pet.can_be_fed(&env) {
-        return Err(StdError::generic_err("It's not feeding time yet. "));
+
+    let owner = env.message.sender.clone();
+    let pet = adopt_pet(deps, &env, &owner, metadata)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "adopt"),
+            log("pet_id", pet.id),
+            log("owner", owner),
+        ],
+    })
+}
+
+pub fn try_paid_adopt<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+    amount: Uint128,
+    metadata: PetMetadata,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let price = state.adoption_price.ok_or_else(|| {
+        StdError::generic_err("Adoption is free. Use the adopt message instead. ")
+    })?;
+    if amount != price {
+        return Err(StdError::generic_err(format!(
+            "Adoption costs exactly {} Food. ",
+            price
+        )));
     }
 
-    pet.last_fed = env.block.time;
-    config(&mut deps.storage).save(&state)?;
+    let pet = adopt_pet(deps, &env, &from, metadata)?;
 
     let burn_msg = snip20::burn_msg(
         amount,
@@ -100,70 +425,3894 @@ pub fn try_feed<S: Storage, A: Api, Q: Querier>(
         messages: vec![burn_msg],
         data: None,
         log: vec![
-            log("action", "feed"),
+            log("action", "adopt"),
+            log("pet_id", pet.id),
+            log("owner", from),
             log("food_amount", amount),
-            log("time", env.block.time),
         ],
     })
 }
 
-pub fn query<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
-    msg: QueryMsg,
-) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::LastFed {} => query_last_fed(&deps.storage),
-        QueryMsg::PetInfo {} => query_pet_info(&deps.storage),
-        QueryMsg::AcceptedToken {} => query_accepted_token(&deps.storage),
+fn adopt_pet<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    owner: &HumanAddr,
+    metadata: PetMetadata,
+) -> StdResult<Pet> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_pet_limit(&deps.storage, &state, env, owner)?;
+    check_pet_metadata(&deps.storage, &state, &metadata, None)?;
+
+    // check_stages makes sure the first stage starts at age 0
+    let first_stage = &state.stages[0];
+    let pet = Pet {
+        id: state.pet_count,
+        name: metadata.name,
+        description: metadata.description,
+        image_uri: metadata.image_uri,
+        owner: owner.clone(),
+        born_at: env.block.time,
+        adopted_at: env.block.time,
+        stage: first_stage.stage,
+        last_fed: env.block.time,
+        allowed_feed_timespan: first_stage.allowed_feed_timespan,
+        total_saturation_time: first_stage.total_saturation_time,
+        caretakers: vec![],
+        approvals: vec![],
+        stats: Stats::full(env.block.time),
+        stat_decay: state.stat_decay.clone(),
+        deaths: 0,
+        died_at: None,
+        paused_duration: state.paused_duration,
+        woke_at: None,
+        feeding_streak: 0,
+        streak_started_at: env.block.time,
+        saturation_penalty: 0,
+        achievements: vec![],
+        generation: 0,
+        parents: vec![],
+        bred_at: None,
+        breeding_partner: None,
+    };
+    save_pet(&mut deps.storage, &pet)?;
+    add_owned_pet(&mut deps.storage, owner, pet.id)?;
+    update_pet_ranking(&mut deps.storage, &pet)?;
+    write_pet_name(&mut deps.storage, &pet.name, pet.id)?;
+    store_pet_event(
+        &mut deps.storage,
+        pet.id,
+        PetEvent::Born {
+            owner: owner.clone(),
+        },
+        &env.block,
+    )?;
+
+    state.pet_count += 1;
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(pet)
+}
+
+// a pet is in the oldest pets leaderboard while it's alive and its owner didn't opt out
+fn update_pet_ranking<S: Storage>(storage: &mut S, pet: &Pet) -> StdResult<()> {
+    let opt_out = load_owner_record(storage, &pet.owner)?.leaderboard_opt_out;
+    let alive = pet.died_at.is_none() && !is_death_passed(storage, pet.id)?;
+    set_pet_ranked(storage, pet.id, alive && !opt_out)
+}
+
+fn check_pet_limit<S: Storage>(
+    storage: &S,
+    state: &State,
+    env: &Env,
+    owner: &HumanAddr,
+) -> StdResult<()> {
+    if let Some(max_pets) = state.max_pets_per_owner {
+        let time = state.pet_time(env.block.time);
+        let mut living_pets = 0;
+        for pet_id in read_owned_pets(storage, owner)? {
+            let mut pet = load_pet(storage, pet_id)?;
+            pet.evolve(time, &state.stages);
+            if !pet.is_dead_at(time) {
+                living_pets += 1;
+            }
+        }
+        if living_pets >= max_pets {
+            return Err(StdError::generic_err(format!(
+                "An owner can't have more than {} living pets. ",
+                max_pets
+            )));
+        }
     }
+
+    Ok(())
 }
 
-fn query_last_fed<S: Storage>(storage: &S) -> QueryResult {
-    let state = config_read(storage).load()?;
-    to_binary(&QueryResponse::LastFedResponse {
-        timestamp: state.pet.last_fed,
+pub fn try_revive<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let pet = load_pet(&deps.storage, pet_id)?;
+    if let Some(price) = state.revive_price_for(&pet)? {
+        return Err(StdError::generic_err(format!(
+            "Reviving this pet costs {} Food. Send the Food with a revive msg instead. ",
+            price
+        )));
+    }
+
+    let owner = env.message.sender.clone();
+    let (pet, unlocked) = revive_pet(deps, &env, &owner, pet)?;
+
+    let mut logs = vec![
+        log("action", "revive"),
+        log("pet_id", pet_id),
+        log("deaths", pet.deaths),
+    ];
+    for achievement in unlocked {
+        logs.push(log("achievement", achievement.as_str()));
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: logs,
     })
 }
-fn query_accepted_token<S: Storage>(storage: &S) -> QueryResult {
-    let state = config_read(storage).load()?;
-    to_binary(&QueryResponse::AcceptedToken {
-        address: state.accepted_token.address,
-        hash: state.accepted_token.hash,
+
+pub fn try_paid_revive<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+    amount: Uint128,
+    pet_id: u64,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let pet = load_pet(&deps.storage, pet_id)?;
+    let price = state.revive_price_for(&pet)?.ok_or_else(|| {
+        StdError::generic_err("Reviving is free. Use the revive message instead. ")
+    })?;
+    if amount != price {
+        return Err(StdError::generic_err(format!(
+            "Reviving this pet costs exactly {} Food. ",
+            price
+        )));
+    }
+
+    let (pet, unlocked) = revive_pet(deps, &env, &from, pet)?;
+
+    let burn_msg = snip20::burn_msg(
+        amount,
+        None,
+        RESPONSE_BLOCK_SIZE,
+        state.accepted_token.hash.clone(),
+        state.accepted_token.address.clone(),
+    )?;
+
+    let mut logs = vec![
+        log("action", "revive"),
+        log("pet_id", pet_id),
+        log("deaths", pet.deaths),
+        log("food_amount", amount),
+    ];
+    for achievement in unlocked {
+        logs.push(log("achievement", achievement.as_str()));
+    }
+
+    Ok(HandleResponse {
+        messages: vec![burn_msg],
+        data: None,
+        log: logs,
     })
 }
-fn query_pet_info<S: Storage>(storage: &S) -> QueryResult {
-    let state = config_read(storage).load()?;
-    to_binary(&QueryResponse::PetInfoResponse {
-        allowed_feed_timespan: state.pet.allowed_feed_timespan,
-        total_saturation_time: state.pet.total_saturation_time,
+
+fn revive_pet<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    owner: &HumanAddr,
+    mut pet: Pet,
+) -> StdResult<(Pet, Vec<Achievement>)> {
+    let state = config_read(&deps.storage).load()?;
+    check_if_owner(&pet, owner)?;
+    if !pet.is_dead(env) {
+        return Err(StdError::generic_err("Only a dead pet can be revived. "));
+    }
+    check_pet_limit(&deps.storage, &state, env, owner)?;
+
+    bury_pet(&mut deps.storage, &mut pet, &env.block)?;
+    pet.revive(env.block.time);
+    store_pet_event(
+        &mut deps.storage,
+        pet.id,
+        PetEvent::Revived { deaths: pet.deaths },
+        &env.block,
+    )?;
+    if pet.evolve(env.block.time, &state.stages) {
+        let event = PetEvent::Evolved { stage: pet.stage };
+        store_pet_event(&mut deps.storage, pet.id, event, &env.block)?;
+    }
+    let unlocked = pet.unlock_achievements(env.block.time);
+    save_pet(&mut deps.storage, &pet)?;
+    update_pet_ranking(&mut deps.storage, &pet)?;
+
+    Ok((pet, unlocked))
+}
+
+pub fn try_rename<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    metadata: PetMetadata,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    if let Some(price) = state.rename_price {
+        return Err(StdError::generic_err(format!(
+            "Renaming costs {} Food. Send the Food with a rename msg instead. ",
+            price
+        )));
+    }
+
+    let owner = env.message.sender.clone();
+    let pet = rename_pet(deps, &env, &owner, pet_id, metadata)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "rename"),
+            log("pet_id", pet_id),
+            log("name", pet.name),
+        ],
     })
 }
-#[cfg(test)]
-mod tests {
-    use cosmwasm_std::{
-        testing::{mock_dependencies, mock_env, MOCK_CONTRACT_ADDR},
-        HumanAddr,
+
+pub fn try_paid_rename<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+    amount: Uint128,
+    pet_id: u64,
+    metadata: PetMetadata,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let price = state.rename_price.ok_or_else(|| {
+        StdError::generic_err("Renaming is free. Use the rename message instead. ")
+    })?;
+    if amount != price {
+        return Err(StdError::generic_err(format!(
+            "Renaming costs exactly {} Food. ",
+            price
+        )));
+    }
+
+    let pet = rename_pet(deps, &env, &from, pet_id, metadata)?;
+
+    let burn_msg = snip20::burn_msg(
+        amount,
+        None,
+        RESPONSE_BLOCK_SIZE,
+        state.accepted_token.hash.clone(),
+        state.accepted_token.address.clone(),
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![burn_msg],
+        data: None,
+        log: vec![
+            log("action", "rename"),
+            log("pet_id", pet_id),
+            log("name", pet.name),
+            log("food_amount", amount),
+        ],
+    })
+}
+
+fn rename_pet<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    owner: &HumanAddr,
+    pet_id: u64,
+    metadata: PetMetadata,
+) -> StdResult<Pet> {
+    let state = config_read(&deps.storage).load()?;
+    let mut pet = load_pet(&deps.storage, pet_id)?;
+    check_if_owner(&pet, owner)?;
+    check_pet_metadata(&deps.storage, &state, &metadata, Some(pet_id))?;
+
+    if metadata.name != pet.name {
+        remove_pet_name(&mut deps.storage, &pet.name, pet_id)?;
+        write_pet_name(&mut deps.storage, &metadata.name, pet_id)?;
+        let event = PetEvent::Renamed {
+            name: metadata.name.clone(),
+        };
+        store_pet_event(&mut deps.storage, pet_id, event, &env.block)?;
+    }
+    pet.name = metadata.name;
+    pet.description = metadata.description;
+    pet.image_uri = metadata.image_uri;
+    save_pet(&mut deps.storage, &pet)?;
+
+    Ok(pet)
+}
+
+// pet_id is the pet being renamed, which may keep its own name
+fn check_pet_metadata<S: Storage>(
+    storage: &S,
+    state: &State,
+    metadata: &PetMetadata,
+    pet_id: Option<u64>,
+) -> StdResult<()> {
+    if !is_valid_name(&metadata.name) {
+        return Err(StdError::generic_err(
+            "Name is not in the expected format (3-30 letters, digits, spaces, - or ')",
+        ));
+    }
+    if state.unique_names {
+        if let Some(owner_id) = read_pet_name(storage, &metadata.name)? {
+            if Some(owner_id) != pet_id {
+                return Err(StdError::generic_err(format!(
+                    "The name {} is already taken. ",
+                    metadata.name
+                )));
+            }
+        }
+    }
+    if let Some(description) = &metadata.description {
+        if !is_valid_description(description) {
+            return Err(StdError::generic_err(
+                "Description is not in the expected format (at most 280 characters)",
+            ));
+        }
+    }
+    if let Some(image_uri) = &metadata.image_uri {
+        if !is_valid_image_uri(image_uri) {
+            return Err(StdError::generic_err(
+                "Image uri is not in the expected format (https:// or ipfs://, at most 256 characters)",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn try_breed<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    parent_a: u64,
+    parent_b: u64,
+    name: String,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    if let Some(price) = state.breed_price {
+        return Err(StdError::generic_err(format!(
+            "Breeding costs {} Food. Send the Food with a breed msg instead. ",
+            price
+        )));
+    }
+
+    let owner = env.message.sender.clone();
+    let offspring = breed_pets(deps, &env, &owner, parent_a, parent_b, name)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "breed"),
+            log("pet_id", offspring.id),
+            log("parent_a", parent_a),
+            log("parent_b", parent_b),
+            log("owner", owner),
+        ],
+    })
+}
+
+pub fn try_paid_breed<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+    amount: Uint128,
+    parent_a: u64,
+    parent_b: u64,
+    name: String,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let price = state.breed_price.ok_or_else(|| {
+        StdError::generic_err("Breeding is free. Use the breed message instead. ")
+    })?;
+    if amount != price {
+        return Err(StdError::generic_err(format!(
+            "Breeding costs exactly {} Food. ",
+            price
+        )));
+    }
+
+    let offspring = breed_pets(deps, &env, &from, parent_a, parent_b, name)?;
+
+    let burn_msg = snip20::burn_msg(
+        amount,
+        None,
+        RESPONSE_BLOCK_SIZE,
+        state.accepted_token.hash,
+        state.accepted_token.address,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![burn_msg],
+        data: None,
+        log: vec![
+            log("action", "breed"),
+            log("pet_id", offspring.id),
+            log("parent_a", parent_a),
+            log("parent_b", parent_b),
+            log("owner", from),
+            log("food_amount", amount),
+        ],
+    })
+}
+
+// The owner of parent_a breeds, the owner of parent_b agrees by picking parent_a as the
+// breeding partner. The offspring inherits the stat decay of its parents
+fn breed_pets<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    owner: &HumanAddr,
+    parent_a: u64,
+    parent_b: u64,
+    name: String,
+) -> StdResult<Pet> {
+    let state = config_read(&deps.storage).load()?;
+    if parent_a == parent_b {
+        return Err(StdError::generic_err("A pet can't breed with itself. "));
+    }
+    let mut pet_a = load_pet(&deps.storage, parent_a)?;
+    let mut pet_b = load_pet(&deps.storage, parent_b)?;
+    check_if_owner(&pet_a, owner)?;
+    let consent = BreedingPartner {
+        pet_id: parent_a,
+        owner: owner.clone(),
     };
+    if &pet_b.owner != owner && pet_b.breeding_partner != Some(consent) {
+        return Err(StdError::generic_err(format!(
+            "The owner of pet {} didn't agree to breed it with pet {}. ",
+            parent_b, parent_a
+        )));
+    }
+    let evolved_a = pet_a.evolve(env.block.time, &state.stages);
+    let evolved_b = pet_b.evolve(env.block.time, &state.stages);
+    check_can_breed(&pet_a, env, &state)?;
+    check_can_breed(&pet_b, env, &state)?;
 
-    use crate::{msg::InitMsg, state::SecretToken};
+    // 16 here represents the lengths in bytes of the block height and time, and of the parent ids
+    let entropy_len = 16 + owner.len() + 16;
+    let mut entropy = Vec::with_capacity(entropy_len);
+    entropy.extend_from_slice(&env.block.height.to_be_bytes());
+    entropy.extend_from_slice(&env.block.time.to_be_bytes());
+    entropy.extend_from_slice(owner.0.as_bytes());
+    entropy.extend_from_slice(&parent_a.to_be_bytes());
+    entropy.extend_from_slice(&parent_b.to_be_bytes());
+    let random = Prng::new(&state.prng_seed, &entropy).rand_bytes();
 
-    use super::init;
+    let metadata = PetMetadata {
+        name,
+        description: None,
+        image_uri: None,
+    };
+    let mut offspring = adopt_pet(deps, env, owner, metadata)?;
+    offspring.stat_decay = StatDecay::inherit(&pet_a.stat_decay, &pet_b.stat_decay, &random);
+    offspring.generation = std::cmp::max(pet_a.generation, pet_b.generation) + 1;
+    offspring.parents = vec![parent_a, parent_b];
+    save_pet(&mut deps.storage, &offspring)?;
 
-    #[test]
-    fn test_init() {
-        let mut deps = mock_dependencies(20, &[]);
-        let env = mock_env("sender", &[]);
-        let msg = InitMsg {
-            accepted_token: SecretToken {
-                address: HumanAddr::from(MOCK_CONTRACT_ADDR),
-                hash: "".to_string(),
-                viewing_key: "supersecret".to_string(),
-            },
-            admin: None,
-            allowed_feed_timespan: 3600,
-            total_saturation_time: 14200,
+    let parents = vec![(pet_a, parent_b, evolved_a), (pet_b, parent_a, evolved_b)];
+    for (mut parent, partner, evolved) in parents {
+        parent.bred_at = Some(env.block.time);
+        parent.breeding_partner = None;
+        save_pet(&mut deps.storage, &parent)?;
+        if evolved {
+            let event = PetEvent::Evolved {
+                stage: parent.stage,
+            };
+            store_pet_event(&mut deps.storage, parent.id, event, &env.block)?;
+        }
+        let event = PetEvent::Bred {
+            partner,
+            offspring: offspring.id,
         };
+        store_pet_event(&mut deps.storage, parent.id, event, &env.block)?;
+    }
+
+    Ok(offspring)
+}
+
+fn check_can_breed(pet: &Pet, env: &Env, state: &State) -> StdResult<()> {
+    if pet.is_dead(env) {
+        return Err(StdError::generic_err(format!(
+            "Pet {} is dead and can't breed. ",
+            pet.id
+        )));
+    }
+    if pet.is_asleep_at(env.block.time) {
+        return Err(StdError::generic_err(format!(
+            "Pet {} is sleeping. Let it rest. ",
+            pet.id
+        )));
+    }
+    if pet.stage != Stage::Adult && pet.stage != Stage::Elder {
+        return Err(StdError::generic_err(format!(
+            "Pet {} is too young to breed. ",
+            pet.id
+        )));
+    }
+    if !pet.can_breed_at(env.block.time, state.breeding_cooldown) {
+        return Err(StdError::generic_err(format!(
+            "Pet {} bred recently and needs to rest before breeding again. ",
+            pet.id
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn try_set_breeding_partner<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    partner: Option<u64>,
+) -> StdResult<HandleResponse> {
+    let mut pet = load_pet(&deps.storage, pet_id)?;
+    check_if_owner(&pet, &env.message.sender)?;
+    pet.breeding_partner = match partner {
+        Some(partner) => Some(BreedingPartner {
+            pet_id: partner,
+            owner: load_pet(&deps.storage, partner)?.owner,
+        }),
+        None => None,
+    };
+    save_pet(&mut deps.storage, &pet)?;
+
+    let mut logs = vec![log("action", "set_breeding_partner"), log("pet_id", pet_id)];
+    if let Some(partner) = partner {
+        logs.push(log("partner", partner));
+    }
 
-        let _res = init(&mut deps, env.clone(), msg).unwrap();
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: logs,
+    })
+}
+
+pub fn try_transfer_pet<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    recipient: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let mut pet = load_pet(&deps.storage, pet_id)?;
+    check_if_owner(&pet, &env.message.sender)?;
+    transfer_pet(deps, &env, &mut pet, &recipient)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "transfer_pet"),
+            log("pet_id", pet_id),
+            log("from", env.message.sender),
+            log("recipient", recipient),
+        ],
+    })
+}
+
+// the pet keeps its state, only the caretakers and approvals of the previous owner are dropped.
+// Returns the previous owner
+fn transfer_pet<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    pet: &mut Pet,
+    recipient: &HumanAddr,
+) -> StdResult<HumanAddr> {
+    let state = config_read(&deps.storage).load()?;
+    check_pet_limit(&deps.storage, &state, env, recipient)?;
+
+    let from = pet.owner.clone();
+    remove_owned_pet(&mut deps.storage, &from, pet.id)?;
+    add_owned_pet(&mut deps.storage, recipient, pet.id)?;
+    bury_pet(&mut deps.storage, pet, &env.block)?;
+    pet.owner = recipient.clone();
+    pet.caretakers.clear();
+    pet.approvals.clear();
+    pet.breeding_partner = None;
+    save_pet(&mut deps.storage, pet)?;
+    update_pet_ranking(&mut deps.storage, pet)?;
+    store_pet_event(
+        &mut deps.storage,
+        pet.id,
+        PetEvent::Transferred {
+            from: from.clone(),
+            recipient: recipient.clone(),
+        },
+        &env.block,
+    )?;
+
+    Ok(from)
+}
+
+pub fn try_transfer_nft<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    token_id: String,
+    recipient: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let from = transfer_nft(deps, &env, &token_id, &recipient)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: Some(to_binary(&HandleAnswer::TransferNft { status: Success })?),
+        log: vec![
+            log("action", "transfer_nft"),
+            log("token_id", token_id),
+            log("from", from),
+            log("recipient", recipient),
+        ],
+    })
+}
+
+pub fn try_batch_transfer_nft<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    transfers: Vec<Transfer>,
+) -> StdResult<HandleResponse> {
+    let mut logs = vec![log("action", "batch_transfer_nft")];
+    for transfer in transfers {
+        for token_id in &transfer.token_ids {
+            transfer_nft(deps, &env, token_id, &transfer.recipient)?;
+        }
+        logs.push(log("recipient", &transfer.recipient));
+        logs.push(log("token_ids", transfer.token_ids.join(",")));
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: Some(to_binary(&HandleAnswer::BatchTransferNft {
+            status: Success,
+        })?),
+        log: logs,
+    })
+}
+
+pub fn try_send_nft<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    token_id: String,
+    contract: HumanAddr,
+    msg: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let from = transfer_nft(deps, &env, &token_id, &contract)?;
+    let sent = vec![(from.clone(), vec![token_id.clone()])];
+    let messages = receive_nft_msgs(&deps.storage, &env, &contract, sent, msg)?;
+
+    Ok(HandleResponse {
+        messages,
+        data: Some(to_binary(&HandleAnswer::SendNft { status: Success })?),
+        log: vec![
+            log("action", "send_nft"),
+            log("token_id", token_id),
+            log("from", from),
+            log("recipient", contract),
+        ],
+    })
+}
+
+pub fn try_batch_send_nft<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    sends: Vec<Send>,
+) -> StdResult<HandleResponse> {
+    let mut messages = vec![];
+    let mut logs = vec![log("action", "batch_send_nft")];
+    for send in sends {
+        // an operator can send the pets of different owners, they are grouped by previous owner
+        let mut sent: Vec<(HumanAddr, Vec<String>)> = vec![];
+        for token_id in &send.token_ids {
+            let from = transfer_nft(deps, &env, token_id, &send.contract)?;
+            match sent.iter_mut().find(|(owner, _)| owner == &from) {
+                Some((_, token_ids)) => token_ids.push(token_id.clone()),
+                None => sent.push((from, vec![token_id.clone()])),
+            }
+        }
+        messages.extend(receive_nft_msgs(
+            &deps.storage,
+            &env,
+            &send.contract,
+            sent,
+            send.msg,
+        )?);
+        logs.push(log("recipient", &send.contract));
+        logs.push(log("token_ids", send.token_ids.join(",")));
+    }
+
+    Ok(HandleResponse {
+        messages,
+        data: Some(to_binary(&HandleAnswer::BatchSendNft { status: Success })?),
+        log: logs,
+    })
+}
+
+// contracts that didn't register just receive the pets, like any other address. The others
+// are notified of the tokens of every previous owner
+fn receive_nft_msgs<S: Storage>(
+    storage: &S,
+    env: &Env,
+    contract: &HumanAddr,
+    sent: Vec<(HumanAddr, Vec<String>)>,
+    msg: Option<Binary>,
+) -> StdResult<Vec<CosmosMsg>> {
+    let receiver = match read_nft_receiver(storage, contract)? {
+        Some(receiver) => receiver,
+        None => return Ok(vec![]),
+    };
+
+    let mut messages = vec![];
+    for (from, token_ids) in sent {
+        let receive_msg =
+            Snip721ReceiveMsg::new(env.message.sender.clone(), from, token_ids, msg.clone());
+        messages.extend(receive_msg.into_cosmos_msgs(
+            receiver.also_implements_batch_receive_nft,
+            receiver.code_hash.clone(),
+            contract.clone(),
+        )?);
+    }
+    Ok(messages)
+}
+
+// the owner, approved addresses and operators of the owner can transfer the token
+fn transfer_nft<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    token_id: &str,
+    recipient: &HumanAddr,
+) -> StdResult<HumanAddr> {
+    let mut pet = load_pet(&deps.storage, parse_token_id(token_id)?)?;
+    let sender = &env.message.sender;
+    let is_operator = read_operators(&deps.storage, &pet.owner)?
+        .iter()
+        .any(|operator| &operator.spender == sender && !operator.expires.is_expired(&env.block));
+    if !is_operator && !pet.can_be_transferred_by(sender, &env.block) {
+        return Err(StdError::generic_err(format!(
+            "Not authorized to transfer token {}",
+            token_id
+        )));
+    }
+
+    transfer_pet(deps, env, &mut pet, recipient)
+}
+
+pub fn try_approve<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    token_id: String,
+    spender: HumanAddr,
+    expires: Option<Expiration>,
+) -> StdResult<HandleResponse> {
+    let expires = expires.unwrap_or(Expiration::Never);
+    let owner = &env.message.sender;
+    set_approval(&mut deps.storage, owner, &token_id, &spender, Some(expires))?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: Some(to_binary(&HandleAnswer::Approve { status: Success })?),
+        log: vec![
+            log("action", "approve"),
+            log("token_id", token_id),
+            log("spender", spender),
+        ],
+    })
+}
+
+pub fn try_revoke<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    token_id: String,
+    spender: HumanAddr,
+) -> StdResult<HandleResponse> {
+    set_approval(
+        &mut deps.storage,
+        &env.message.sender,
+        &token_id,
+        &spender,
+        None,
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: Some(to_binary(&HandleAnswer::Revoke { status: Success })?),
+        log: vec![
+            log("action", "revoke"),
+            log("token_id", token_id),
+            log("spender", spender),
+        ],
+    })
+}
+
+pub fn try_approve_all<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    operator: HumanAddr,
+    expires: Option<Expiration>,
+) -> StdResult<HandleResponse> {
+    let expires = expires.unwrap_or(Expiration::Never);
+    let owner = &env.message.sender;
+    set_operator(
+        &mut deps.storage,
+        &env.block,
+        owner,
+        &operator,
+        Some(expires),
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: Some(to_binary(&HandleAnswer::ApproveAll { status: Success })?),
+        log: vec![
+            log("action", "approve_all"),
+            log("owner", owner),
+            log("operator", operator),
+        ],
+    })
+}
+
+pub fn try_revoke_all<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    operator: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let owner = &env.message.sender;
+    set_operator(&mut deps.storage, &env.block, owner, &operator, None)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: Some(to_binary(&HandleAnswer::RevokeAll { status: Success })?),
+        log: vec![
+            log("action", "revoke_all"),
+            log("owner", owner),
+            log("operator", operator),
+        ],
+    })
+}
+
+// SNIP-721 whitelisting is mapped on the approvals of single pets and the operators. Ownership
+// is public, so view_owner doesn't change anything
+pub fn try_set_whitelisted_approval<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    address: HumanAddr,
+    token_id: Option<String>,
+    view_private_metadata: Option<AccessLevel>,
+    transfer: Option<AccessLevel>,
+    expires: Option<Expiration>,
+) -> StdResult<HandleResponse> {
+    if let Some(AccessLevel::ApproveToken) | Some(AccessLevel::All) = view_private_metadata {
+        return Err(StdError::generic_err(
+            "The private metadata of a pet is only shown to its owner. ",
+        ));
+    }
+
+    let owner = &env.message.sender;
+    let expires = expires.unwrap_or(Expiration::Never);
+    let single_token = || {
+        token_id.as_ref().ok_or_else(|| {
+            StdError::generic_err("A token_id is needed to approve or revoke a single pet. ")
+        })
+    };
+    match transfer {
+        Some(AccessLevel::ApproveToken) => set_approval(
+            &mut deps.storage,
+            owner,
+            single_token()?,
+            &address,
+            Some(expires),
+        )?,
+        Some(AccessLevel::RevokeToken) => {
+            set_approval(&mut deps.storage, owner, single_token()?, &address, None)?
+        }
+        Some(AccessLevel::All) => set_operator(
+            &mut deps.storage,
+            &env.block,
+            owner,
+            &address,
+            Some(expires),
+        )?,
+        Some(AccessLevel::None) => {
+            set_operator(&mut deps.storage, &env.block, owner, &address, None)?;
+            for pet_id in read_owned_pets(&deps.storage, owner)? {
+                let mut pet = load_pet(&deps.storage, pet_id)?;
+                if pet.approvals.iter().any(|a| a.spender == address) {
+                    pet.approvals.retain(|a| a.spender != address);
+                    save_pet(&mut deps.storage, &pet)?;
+                }
+            }
+        }
+        None => {}
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: Some(to_binary(&HandleAnswer::SetWhitelistedApproval {
+            status: Success,
+        })?),
+        log: vec![
+            log("action", "set_whitelisted_approval"),
+            log("owner", owner),
+            log("address", address),
+        ],
+    })
+}
+
+// approves the spender for the pet, or revokes its approval when expires is None
+fn set_approval<S: Storage>(
+    storage: &mut S,
+    owner: &HumanAddr,
+    token_id: &str,
+    spender: &HumanAddr,
+    expires: Option<Expiration>,
+) -> StdResult<()> {
+    let mut pet = load_pet(storage, parse_token_id(token_id)?)?;
+    check_if_owner(&pet, owner)?;
+
+    // approving a spender again only updates its expiration
+    pet.approvals.retain(|a| &a.spender != spender);
+    if let Some(expires) = expires {
+        pet.approvals.push(Approval {
+            spender: spender.clone(),
+            expires,
+        });
+    }
+    save_pet(storage, &pet)
+}
+
+// approves the operator for every pet of the owner, or revokes it when expires is None.
+// Expired operators are dropped along the way
+fn set_operator<S: Storage>(
+    storage: &mut S,
+    block: &BlockInfo,
+    owner: &HumanAddr,
+    operator: &HumanAddr,
+    expires: Option<Expiration>,
+) -> StdResult<()> {
+    let mut operators = read_operators(storage, owner)?;
+    operators.retain(|a| &a.spender != operator && !a.expires.is_expired(block));
+    if let Some(expires) = expires {
+        operators.push(Approval {
+            spender: operator.clone(),
+            expires,
+        });
+    }
+    write_operators(storage, owner, &operators)
+}
+
+pub fn try_register_receive_nft<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    code_hash: String,
+    also_implements_batch_receive_nft: Option<bool>,
+) -> StdResult<HandleResponse> {
+    let receiver = NftReceiver {
+        code_hash,
+        also_implements_batch_receive_nft: also_implements_batch_receive_nft.unwrap_or(false),
+    };
+    write_nft_receiver(&mut deps.storage, &env.message.sender, &receiver)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: Some(to_binary(&HandleAnswer::RegisterReceiveNft {
+            status: Success,
+        })?),
+        log: vec![
+            log("action", "register_receive_nft"),
+            log("contract", env.message.sender),
+        ],
+    })
+}
+
+// token ids are the pet ids as strings
+fn parse_token_id(token_id: &str) -> StdResult<u64> {
+    token_id
+        .parse()
+        .map_err(|_| StdError::generic_err(format!("Token id {} does not exist", token_id)))
+}
+
+pub fn try_add_caretaker<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    caretaker: HumanAddr,
+    expiration: Option<u64>,
+) -> StdResult<HandleResponse> {
+    let mut pet = load_pet(&deps.storage, pet_id)?;
+    check_if_owner(&pet, &env.message.sender)?;
+
+    // re-adding a caretaker only updates its expiration
+    pet.caretakers.retain(|c| c.address != caretaker);
+    pet.caretakers.push(Caretaker {
+        address: caretaker.clone(),
+        expiration,
+    });
+    save_pet(&mut deps.storage, &pet)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "add_caretaker"),
+            log("pet_id", pet_id),
+            log("caretaker", caretaker),
+        ],
+    })
+}
+
+pub fn try_remove_caretaker<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    caretaker: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let mut pet = load_pet(&deps.storage, pet_id)?;
+    check_if_owner(&pet, &env.message.sender)?;
+
+    pet.caretakers.retain(|c| c.address != caretaker);
+    save_pet(&mut deps.storage, &pet)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "remove_caretaker"),
+            log("pet_id", pet_id),
+            log("caretaker", caretaker),
+        ],
+    })
+}
+
+pub fn try_set_adoption_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    adoption_price: Option<Uint128>,
+    max_pets_per_owner: Option<u32>,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    state.adoption_price = adoption_price;
+    state.max_pets_per_owner = max_pets_per_owner;
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![log("action", "set_adoption_config")],
+    })
+}
+
+pub fn try_set_revive_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    revive_price: Option<Uint128>,
+    revive_price_increase: Uint128,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    state.revive_price = revive_price;
+    state.revive_price_increase = revive_price_increase;
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![log("action", "set_revive_config")],
+    })
+}
+
+pub fn try_update_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    allowed_feed_timespan: Option<u64>,
+    total_saturation_time: Option<u64>,
+    stages: Option<Vec<StageConfig>>,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    let timespans_changed = allowed_feed_timespan.is_some() || total_saturation_time.is_some();
+    state.allowed_feed_timespan = allowed_feed_timespan.unwrap_or(state.allowed_feed_timespan);
+    state.total_saturation_time = total_saturation_time.unwrap_or(state.total_saturation_time);
+    if let Some(stages) = stages {
+        check_stages(&stages)?;
+        state.stages = stages;
+    } else if timespans_changed {
+        state.stages = default_stages(state.allowed_feed_timespan, state.total_saturation_time);
+    }
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "update_config"),
+            log("allowed_feed_timespan", state.allowed_feed_timespan),
+            log("total_saturation_time", state.total_saturation_time),
+        ],
+    })
+}
+
+pub fn try_change_admin<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    address: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    state.admin = Some(address.clone());
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![log("action", "change_admin"), log("admin", address)],
+    })
+}
+
+pub fn try_set_accepted_token<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    accepted_token: SecretToken,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    state.accepted_token = accepted_token.clone();
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: register_token_msgs(&env, accepted_token.clone())?,
+        data: None,
+        log: vec![
+            log("action", "set_accepted_token"),
+            log("accepted_token", accepted_token.address),
+        ],
+    })
+}
+
+pub fn try_add_food_token<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    food_token: FoodToken,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    if food_token.nutrition > MAX_FOOD_NUTRITION {
+        return Err(StdError::generic_err(format!(
+            "Nutrition can't be higher than {}",
+            MAX_FOOD_NUTRITION
+        )));
+    }
+    if food_saturation(&state, &food_token)? == 0 {
+        return Err(StdError::generic_err(
+            "Nutrition is too low, a unit of this food wouldn't saturate the pet",
+        ));
+    }
+    if food_token.token.address == state.accepted_token.address {
+        return Err(StdError::generic_err(
+            "The accepted token is already a food. ",
+        ));
+    }
+
+    let address = food_token.token.address.clone();
+    state
+        .food_tokens
+        .retain(|food| food.token.address != address);
+    state.food_tokens.push(food_token.clone());
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: register_token_msgs(&env, food_token.token)?,
+        data: None,
+        log: vec![log("action", "add_food_token"), log("food_token", address)],
+    })
+}
+
+// saturation a unit of the food gives, scaled by its nutrition
+fn food_saturation(state: &State, food: &FoodToken) -> StdResult<u64> {
+    state
+        .saturation_per_food
+        .checked_mul(food.nutrition)
+        .map(|saturation| saturation / 100)
+        .ok_or_else(|| StdError::generic_err("The saturation of this food is too large"))
+}
+
+pub fn try_remove_food_token<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    address: HumanAddr,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    state
+        .food_tokens
+        .retain(|food| food.token.address != address);
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "remove_food_token"),
+            log("food_token", address),
+        ],
+    })
+}
+
+pub fn try_set_feeding_game<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    feeding_game: Option<FeedingGame>,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    state.feeding_game = feeding_game;
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![log("action", "set_feeding_game")],
+    })
+}
+
+pub fn try_set_contract_status<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    level: ContractStatusLevel,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    // StopAll also rejects feeding, so the pets are frozen in time until the contract resumes
+    if level == ContractStatusLevel::StopAll {
+        state.pause(env.block.time);
+    } else {
+        state.unpause(env.block.time);
+    }
+    state.contract_status = level;
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![log("action", "set_contract_status")],
+    })
+}
+
+pub fn try_set_leaderboard_opt_out<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    opt_out: bool,
+) -> StdResult<HandleResponse> {
+    let owner = env.message.sender;
+    let mut owner_record = load_owner_record(&deps.storage, &owner)?;
+    owner_record.leaderboard_opt_out = opt_out;
+    save_owner_record(&mut deps.storage, &owner, &owner_record)?;
+    for pet_id in read_owned_pets(&deps.storage, &owner)? {
+        let pet = load_pet(&deps.storage, pet_id)?;
+        update_pet_ranking(&mut deps.storage, &pet)?;
+    }
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "set_leaderboard_opt_out"),
+            log("opt_out", opt_out),
+        ],
+    })
+}
+
+pub fn try_set_key<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    key: String,
+) -> StdResult<HandleResponse> {
+    let vk = ViewingKey(key);
+    write_viewing_key(&mut deps.storage, &env.message.sender, &vk);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![log("action", "set_viewing_key")],
+    })
+}
+
+pub fn try_create_key<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    entropy: String,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let key = ViewingKey::new(&env, &state.prng_seed, entropy.as_ref());
+    write_viewing_key(&mut deps.storage, &env.message.sender, &key);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: Some(to_binary(&HandleAnswer::CreateViewingKey { key })?),
+        log: vec![log("action", "create_viewing_key")],
+    })
+}
+
+fn revoke_permit<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    permit_name: String,
+) -> StdResult<HandleResponse> {
+    RevokedPermits::revoke_permit(
+        &mut deps.storage,
+        PREFIX_REVOKED_PERMITS,
+        &env.message.sender,
+        &permit_name,
+    );
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "revoke_permit"),
+            log("permit_name", permit_name),
+        ],
+    })
+}
+
+pub fn try_feed<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+    amount: Uint128,
+    pet_id: u64,
+    food: FoodToken,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let mut pet = load_pet(&deps.storage, pet_id)?;
+    let evolved = pet.evolve(env.block.time, &state.stages);
+
+    if !pet.can_be_fed_by(&from, &env.block) {
+        return Err(StdError::generic_err(
+            "Only the owner or a caretaker can feed this pet. ",
+        ));
+    }
+    if pet.is_dead(&env) {
+        return Err(StdError::generic_err(
+            "Pet is already dead :(. You forgot to take care of it. ",
+        ));
+    }
+    if pet.is_asleep_at(env.block.time) {
+        return Err(StdError::generic_err("Pet is sleeping. Let it rest. "));
+    }
+    let overfed = !pet.can_be_fed(&env);
+    if overfed && state.feeding_game.is_none() {
+        return Err(StdError::generic_err("It's not feeding time yet. "));
+    }
+
+    // nutrition is checked when the token is added, so every food gives some saturation
+    let saturation_per_food = food_saturation(&state, &food)?;
+    let food_needed = pet.food_needed(&env, saturation_per_food);
+    let eaten = std::cmp::min(amount.u128(), food_needed);
+    // a full pet eats nothing, which must not count as a feeding
+    if eaten == 0 {
+        return Err(StdError::generic_err(
+            "The pet is full and can't eat right now. ",
+        ));
+    }
+    let excess = amount.u128() - eaten;
+    if excess > 0 && !state.refund_excess_food {
+        return Err(StdError::generic_err(format!(
+            "Too much food. The pet can only eat {} right now. ",
+            food_needed
+        )));
+    }
+
+    let on_time = pet.is_fed_on_time(env.block.time, state.feeding_game.as_ref());
+    if let Some(game) = &state.feeding_game {
+        pet.play_feeding_game(env.block.time, game);
+    }
+    pet.record_feeding(on_time, env.block.time);
+    pet.eat(&env, eaten, saturation_per_food);
+    for effect in &food.effects {
+        pet.raise_stat(effect.stat, effect.points, env.block.time);
+    }
+    let mut unlocked = vec![];
+    if pet.unlock(Achievement::FirstFeed) {
+        unlocked.push(Achievement::FirstFeed);
+    }
+    unlocked.extend(pet.unlock_achievements(env.block.time));
+    save_pet(&mut deps.storage, &pet)?;
+
+    // the care score goes to the owner, also when a caretaker feeds the pet
+    let mut owner_record = load_owner_record(&deps.storage, &pet.owner)?;
+    owner_record.record_feeding(on_time);
+    owner_record.care_score += if on_time {
+        CARE_SCORE_ON_TIME_FEEDING
+    } else if overfed {
+        0
+    } else {
+        CARE_SCORE_LATE_FEEDING
+    };
+    save_owner_record(&mut deps.storage, &pet.owner, &owner_record)?;
+    if evolved {
+        let event = PetEvent::Evolved { stage: pet.stage };
+        store_pet_event(&mut deps.storage, pet_id, event, &env.block)?;
+    }
+    store_pet_event(
+        &mut deps.storage,
+        pet_id,
+        PetEvent::Fed {
+            feeder: from.clone(),
+            food_eaten: Uint128(eaten),
+        },
+        &env.block,
+    )?;
+
+    let mut logs = vec![
+        log("action", "feed"),
+        log("pet_id", pet_id),
+        log("food_amount", amount),
+        log("food_eaten", eaten),
+        log("food_refunded", excess),
+        log("food_token", &food.token.address),
+        log("time", env.block.time),
+    ];
+    if evolved {
+        logs.push(log("evolved_to", pet.stage.as_str()));
+    }
+    if state.feeding_game.is_some() {
+        logs.push(log("overfed", overfed));
+    }
+    logs.push(log("feeding_streak", pet.feeding_streak));
+    logs.push(log("owner_feeding_streak", owner_record.feeding_streak));
+    logs.push(log("care_score", owner_record.care_score));
+    for achievement in unlocked {
+        logs.push(log("achievement", achievement.as_str()));
+    }
+
+    let mut messages = vec![snip20::burn_msg(
+        Uint128(eaten),
+        None,
+        RESPONSE_BLOCK_SIZE,
+        food.token.hash.clone(),
+        food.token.address.clone(),
+    )?];
+    if excess > 0 {
+        messages.push(snip20::transfer_msg(
+            from,
+            Uint128(excess),
+            None,
+            RESPONSE_BLOCK_SIZE,
+            food.token.hash.clone(),
+            food.token.address.clone(),
+        )?);
+    }
+
+    Ok(HandleResponse {
+        messages,
+        data: None,
+        log: logs,
+    })
+}
+
+pub fn try_bury<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+) -> StdResult<HandleResponse> {
+    let mut pet = load_pet(&deps.storage, pet_id)?;
+    if !bury_pet(&mut deps.storage, &mut pet, &env.block)? {
+        return Err(StdError::generic_err(
+            "Only a dead pet that isn't buried yet can be buried. ",
+        ));
+    }
+    save_pet(&mut deps.storage, &pet)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "bury"),
+            log("pet_id", pet_id),
+            log("died_at", pet.time_of_death()),
+        ],
+    })
+}
+
+// records the death of the pet in its history and the graveyard, returns false if the pet is
+// alive or its death is already recorded. The caller has to save the pet
+fn bury_pet<S: Storage>(storage: &mut S, pet: &mut Pet, block: &BlockInfo) -> StdResult<bool> {
+    if pet.died_at.is_some() || !pet.is_dead_at(block.time) {
+        return Ok(false);
+    }
+
+    let died_at = pet.time_of_death();
+    pet.died_at = Some(died_at);
+    set_pet_ranked(storage, pet.id, false)?;
+    store_pet_event(storage, pet.id, PetEvent::Died { died_at }, block)?;
+    store_grave(storage, pet, died_at)?;
+
+    let mut owner_record = load_owner_record(storage, &pet.owner)?;
+    owner_record.feeding_streak = 0;
+    owner_record.care_score = owner_record
+        .care_score
+        .saturating_sub(CARE_SCORE_DEATH_PENALTY);
+    save_owner_record(storage, &pet.owner, &owner_record)?;
+    Ok(true)
+}
+
+pub fn try_sleep<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    duration: u64,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    if let Some(price) = state.sleep_price {
+        return Err(StdError::generic_err(format!(
+            "Sleeping costs {} Food. Send the Food with a sleep msg instead. ",
+            price
+        )));
+    }
+
+    let owner = env.message.sender.clone();
+    sleep_pet(deps, &env, &owner, pet_id, duration)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "sleep"),
+            log("pet_id", pet_id),
+            log("duration", duration),
+        ],
+    })
+}
+
+pub fn try_paid_sleep<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: HumanAddr,
+    amount: Uint128,
+    pet_id: u64,
+    duration: u64,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let price = state.sleep_price.ok_or_else(|| {
+        StdError::generic_err("Sleeping is free. Use the sleep message instead. ")
+    })?;
+    if amount != price {
+        return Err(StdError::generic_err(format!(
+            "Sleeping costs exactly {} Food. ",
+            price
+        )));
+    }
+
+    sleep_pet(deps, &env, &from, pet_id, duration)?;
+
+    let burn_msg = snip20::burn_msg(
+        amount,
+        None,
+        RESPONSE_BLOCK_SIZE,
+        state.accepted_token.hash.clone(),
+        state.accepted_token.address.clone(),
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![burn_msg],
+        data: None,
+        log: vec![
+            log("action", "sleep"),
+            log("pet_id", pet_id),
+            log("duration", duration),
+            log("food_amount", amount),
+        ],
+    })
+}
+
+fn sleep_pet<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    owner: &HumanAddr,
+    pet_id: u64,
+    duration: u64,
+) -> StdResult<()> {
+    let state = config_read(&deps.storage).load()?;
+    let mut pet = load_pet(&deps.storage, pet_id)?;
+    check_if_owner(&pet, owner)?;
+
+    if duration == 0 || duration > state.max_sleep_duration {
+        return Err(StdError::generic_err(format!(
+            "A pet can sleep for at most {} seconds. ",
+            state.max_sleep_duration
+        )));
+    }
+    if pet.is_dead(env) {
+        return Err(StdError::generic_err(
+            "Pet is already dead :(. You forgot to take care of it. ",
+        ));
+    }
+    if pet.is_asleep_at(env.block.time) {
+        return Err(StdError::generic_err("Pet is already sleeping. "));
+    }
+    if !pet.can_sleep_at(env.block.time, state.sleep_cooldown) {
+        return Err(StdError::generic_err("Pet isn't tired yet. "));
+    }
+
+    pet.evolve(env.block.time, &state.stages);
+    pet.sleep(env.block.time, duration);
+    save_pet(&mut deps.storage, &pet)
+}
+
+pub fn try_wake<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+) -> StdResult<HandleResponse> {
+    let mut pet = load_pet(&deps.storage, pet_id)?;
+    check_if_owner(&pet, &env.message.sender)?;
+    if !pet.is_asleep_at(env.block.time) {
+        return Err(StdError::generic_err("Pet isn't sleeping. "));
+    }
+
+    pet.wake(env.block.time);
+    save_pet(&mut deps.storage, &pet)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![log("action", "wake"), log("pet_id", pet_id)],
+    })
+}
+
+pub fn try_set_sleep_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    sleep_price: Option<Uint128>,
+    max_sleep_duration: u64,
+    sleep_cooldown: u64,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    state.sleep_price = sleep_price;
+    state.max_sleep_duration = max_sleep_duration;
+    state.sleep_cooldown = sleep_cooldown;
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![log("action", "set_sleep_config")],
+    })
+}
+
+pub fn try_set_breeding_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    breed_price: Option<Uint128>,
+    breeding_cooldown: u64,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    state.breed_price = breed_price;
+    state.breeding_cooldown = breeding_cooldown;
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![log("action", "set_breeding_config")],
+    })
+}
+
+pub fn try_set_name_config<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    rename_price: Option<Uint128>,
+    unique_names: bool,
+) -> StdResult<HandleResponse> {
+    let mut state = config_read(&deps.storage).load()?;
+    check_if_admin(&state, &env.message.sender)?;
+
+    state.rename_price = rename_price;
+    state.unique_names = unique_names;
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![log("action", "set_name_config")],
+    })
+}
+
+pub fn try_care<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    pet_id: u64,
+    action: CareAction,
+) -> StdResult<HandleResponse> {
+    let mut pet = load_pet(&deps.storage, pet_id)?;
+
+    if !pet.can_be_fed_by(&env.message.sender, &env.block) {
+        return Err(StdError::generic_err(
+            "Only the owner or a caretaker can take care of this pet. ",
+        ));
+    }
+    if pet.is_dead(&env) {
+        return Err(StdError::generic_err(
+            "Pet is already dead :(. You forgot to take care of it. ",
+        ));
+    }
+    if pet.is_asleep_at(env.block.time) {
+        return Err(StdError::generic_err("Pet is sleeping. Let it rest. "));
+    }
+
+    pet.care(action, env.block.time);
+    save_pet(&mut deps.storage, &pet)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        data: None,
+        log: vec![
+            log("action", "care"),
+            log("pet_id", pet_id),
+            log("care_action", action.as_str()),
+            log("time", env.block.time),
+        ],
+    })
+}
+
+fn check_if_owner(pet: &Pet, account: &HumanAddr) -> StdResult<()> {
+    if &pet.owner != account {
+        return Err(StdError::generic_err(
+            "Only the owner of the pet can run this command",
+        ));
+    }
+
+    Ok(())
+}
+
+fn check_if_admin(state: &State, account: &HumanAddr) -> StdResult<()> {
+    if state.admin.as_ref() != Some(account) {
+        return Err(StdError::generic_err(
+            "This is an admin command. Admin commands can only be run from admin address",
+        ));
+    }
+
+    Ok(())
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let len = name.chars().count();
+    let len_is_valid = (3..=30).contains(&len);
+
+    len_is_valid
+        && name.trim() == name
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '\'')
+}
+
+fn is_valid_description(description: &str) -> bool {
+    description.chars().count() <= 280
+}
+
+fn is_valid_image_uri(image_uri: &str) -> bool {
+    let scheme_is_valid = image_uri.starts_with("https://") || image_uri.starts_with("ipfs://");
+
+    scheme_is_valid && image_uri.len() <= 256 && !image_uri.contains(char::is_whitespace)
+}
+
+pub fn query<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::PetSummary { pet_id } => query_pet_summary(&deps.storage, pet_id),
+        QueryMsg::WithPermit { permit, query } => permit_queries(deps, permit, query),
+        QueryMsg::Pets { owner } => query_pets(&deps.storage, &owner),
+        QueryMsg::Graveyard { page, page_size } => {
+            query_graveyard(&deps.storage, page.unwrap_or(0), page_size)
+        }
+        QueryMsg::OldestPets { page, page_size } => {
+            query_oldest_pets(&deps.storage, page.unwrap_or(0), page_size)
+        }
+        QueryMsg::BestCaretakers { page, page_size } => {
+            query_best_caretakers(&deps.storage, page.unwrap_or(0), page_size)
+        }
+        QueryMsg::Stages {} => query_stages(&deps.storage),
+        QueryMsg::AdoptionConfig {} => query_adoption_config(&deps.storage),
+        QueryMsg::ReviveConfig {} => query_revive_config(&deps.storage),
+        QueryMsg::SleepConfig {} => query_sleep_config(&deps.storage),
+        QueryMsg::NameConfig {} => query_name_config(&deps.storage),
+        QueryMsg::BreedingConfig {} => query_breeding_config(&deps.storage),
+        QueryMsg::AcceptedToken {} => query_accepted_token(&deps.storage),
+        QueryMsg::FoodTokens {} => query_food_tokens(&deps.storage),
+        QueryMsg::FeedingGame {} => query_feeding_game(&deps.storage),
+        QueryMsg::ContractStatus {} => query_contract_status(&deps.storage),
+        QueryMsg::ContractInfo {} => query_contract_info(),
+        QueryMsg::NumTokens {} => query_num_tokens(&deps.storage),
+        QueryMsg::AllTokens { start_after, limit } => {
+            query_all_tokens(&deps.storage, start_after, limit)
+        }
+        QueryMsg::Tokens {
+            owner,
+            start_after,
+            limit,
+        } => query_tokens(&deps.storage, &owner, start_after, limit),
+        QueryMsg::OwnerOf { token_id } => query_owner_of(&deps.storage, &token_id),
+        QueryMsg::NftInfo { token_id } => query_nft_info(&deps.storage, &token_id),
+        QueryMsg::PrivateMetadata { viewer: None, .. } => Err(StdError::generic_err(
+            "The private metadata is only shown to the owner, set a viewer or use a permit. ",
+        )),
+        _ => viewing_keys_queries(deps, msg),
+    }
+}
+
+fn permit_queries<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    permit: Permit,
+    query: QueryWithPermit,
+) -> QueryResult {
+    // Validate permit content
+    let contract_address = config_read(&deps.storage).load()?.contract_address;
+    let account = validate(deps, PREFIX_REVOKED_PERMITS, &permit, contract_address)?;
+
+    // every private query shows when the owner is active, so they all need the owner permission
+    if !permit.check_permission(&Permission::Owner) {
+        return Err(StdError::generic_err(format!(
+            "No permission to query pets, got permissions {:?}",
+            permit.params.permissions
+        )));
+    }
+
+    // Permit validated! We can now execute the query.
+    match query {
+        QueryWithPermit::LastFed { pet_id } => query_last_fed(&deps.storage, &account, pet_id),
+        QueryWithPermit::PetInfo { pet_id } => query_pet_info(&deps.storage, &account, pet_id),
+        QueryWithPermit::PetStatus {
+            pet_id,
+            current_time,
+        } => query_pet_status(&deps.storage, &account, pet_id, current_time),
+        QueryWithPermit::PetHistory {
+            pet_id,
+            page,
+            page_size,
+        } => query_pet_history(
+            &deps.storage,
+            &account,
+            pet_id,
+            page.unwrap_or(0),
+            page_size,
+        ),
+        QueryWithPermit::OwnerRecord {} => query_owner_record(&deps.storage, &account),
+        QueryWithPermit::PrivateMetadata { token_id } => {
+            query_private_metadata(&deps.storage, &account, &token_id)
+        }
+    }
+}
+
+pub fn viewing_keys_queries<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    msg: QueryMsg,
+) -> QueryResult {
+    let (address, key) = msg.get_validation_params();
+
+    let authenticated = match read_viewing_key(&deps.storage, address) {
+        Some(expected_key) => key.check_viewing_key(expected_key.as_slice()),
+        None => {
+            // Checking the key will take significant time. We don't want to exit immediately if it isn't set
+            // in a way which will allow to time the command and determine if a viewing key doesn't exist
+            key.check_viewing_key(&[0u8; VIEWING_KEY_SIZE]);
+            false
+        }
+    };
+
+    if authenticated {
+        return match msg {
+            QueryMsg::LastFed {
+                pet_id, address, ..
+            } => query_last_fed(&deps.storage, &address, pet_id),
+            QueryMsg::PetInfo {
+                pet_id, address, ..
+            } => query_pet_info(&deps.storage, &address, pet_id),
+            QueryMsg::PetStatus {
+                pet_id,
+                current_time,
+                address,
+                ..
+            } => query_pet_status(&deps.storage, &address, pet_id, current_time),
+            QueryMsg::PetHistory {
+                pet_id,
+                page,
+                page_size,
+                address,
+                ..
+            } => query_pet_history(
+                &deps.storage,
+                &address,
+                pet_id,
+                page.unwrap_or(0),
+                page_size,
+            ),
+            QueryMsg::OwnerRecord { address, .. } => query_owner_record(&deps.storage, &address),
+            QueryMsg::PrivateMetadata {
+                token_id,
+                viewer: Some(viewer),
+            } => query_private_metadata(&deps.storage, &viewer.address, &token_id),
+            _ => panic!("This query type does not require authentication"),
+        };
+    }
+
+    to_binary(&QueryResponse::ViewingKeyError {
+        msg: "Wrong viewing key for this address or viewing key not set".to_string(),
+    })
+}
+
+fn query_pet_summary<S: Storage>(storage: &S, pet_id: u64) -> QueryResult {
+    let pet = load_pet(storage, pet_id)?;
+    to_binary(&QueryResponse::PetSummary {
+        id: pet.id,
+        name: pet.name,
+        description: pet.description,
+        image_uri: pet.image_uri,
+        owner: pet.owner,
+        born_at: pet.adopted_at,
+        deaths: pet.deaths,
+        generation: pet.generation,
+        parents: pet.parents,
+    })
+}
+
+fn query_contract_info() -> QueryResult {
+    to_binary(&NftQueryAnswer::ContractInfo {
+        name: NFT_NAME.to_string(),
+        symbol: NFT_SYMBOL.to_string(),
+    })
+}
+
+fn query_num_tokens<S: Storage>(storage: &S) -> QueryResult {
+    // pets are never destroyed, dead pets stay tokens that can be revived
+    let state = config_read(storage).load()?;
+    to_binary(&NftQueryAnswer::NumTokens {
+        count: state.pet_count as u32,
+    })
+}
+
+fn query_all_tokens<S: Storage>(
+    storage: &S,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> QueryResult {
+    let state = config_read(storage).load()?;
+    let pet_ids = (0..state.pet_count).collect::<Vec<u64>>();
+    let tokens = token_list(&pet_ids, start_after, limit)?;
+    to_binary(&NftQueryAnswer::TokenList { tokens })
+}
+
+fn query_tokens<S: Storage>(
+    storage: &S,
+    owner: &HumanAddr,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> QueryResult {
+    let mut pet_ids = read_owned_pets(storage, owner)?;
+    pet_ids.sort_unstable();
+    let tokens = token_list(&pet_ids, start_after, limit)?;
+    to_binary(&NftQueryAnswer::TokenList { tokens })
+}
+
+// the token ids of the sorted pet ids after start_after
+fn token_list(
+    pet_ids: &[u64],
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let start_after = match start_after {
+        Some(token_id) => Some(parse_token_id(&token_id)?),
+        None => None,
+    };
+    let limit = limit.unwrap_or(DEFAULT_TOKEN_LIST_LIMIT) as usize;
+
+    Ok(pet_ids
+        .iter()
+        .filter(|&&pet_id| start_after.map(|start| pet_id > start).unwrap_or(true))
+        .take(limit)
+        .map(|pet_id| pet_id.to_string())
+        .collect())
+}
+
+fn query_owner_of<S: Storage>(storage: &S, token_id: &str) -> QueryResult {
+    let pet = load_pet(storage, parse_token_id(token_id)?)?;
+    to_binary(&NftQueryAnswer::OwnerOf {
+        owner: pet.owner,
+        approvals: pet.approvals,
+    })
+}
+
+// the same details as PetSummary, the stage and achievements follow the feedings and are private
+fn query_nft_info<S: Storage>(storage: &S, token_id: &str) -> QueryResult {
+    let pet = load_pet(storage, parse_token_id(token_id)?)?;
+    let attributes = vec![
+        nft_trait("born_at", pet.adopted_at, Some("date")),
+        nft_trait("deaths", pet.deaths, Some("number")),
+        nft_trait("generation", pet.generation, Some("number")),
+    ];
+
+    to_binary(&NftQueryAnswer::NftInfo {
+        token_uri: None,
+        extension: Some(Extension {
+            name: Some(pet.name),
+            description: pet.description,
+            image: pet.image_uri,
+            attributes: Some(attributes),
+        }),
+    })
+}
+
+// The status of the pet is read from its own storage, so it always moves with the token. It is
+// the status of the last update of the pet, the live hunger and age come from PetStatus
+fn query_private_metadata<S: Storage>(
+    storage: &S,
+    account: &HumanAddr,
+    token_id: &str,
+) -> QueryResult {
+    let pet = load_pet(storage, parse_token_id(token_id)?)?;
+    check_if_owner(&pet, account)?;
+
+    let mut attributes = vec![
+        nft_trait("last_fed", pet.last_fed, Some("date")),
+        nft_trait("feeding_streak", pet.feeding_streak, Some("number")),
+        nft_trait("stage", pet.stage.as_str(), None),
+    ];
+    for achievement in pet.achievements.iter() {
+        attributes.push(nft_trait("achievement", achievement.as_str(), None));
+    }
+    let stats = &pet.stats;
+    attributes.push(nft_trait(
+        "happiness",
+        stats.happiness.value,
+        Some("number"),
+    ));
+    attributes.push(nft_trait("health", stats.health.value, Some("number")));
+    attributes.push(nft_trait("hygiene", stats.hygiene.value, Some("number")));
+    attributes.push(nft_trait("energy", stats.energy.value, Some("number")));
+
+    to_binary(&NftQueryAnswer::PrivateMetadata {
+        token_uri: None,
+        extension: Some(Extension {
+            name: Some(pet.name),
+            description: pet.description,
+            image: pet.image_uri,
+            attributes: Some(attributes),
+        }),
+    })
+}
+
+fn nft_trait<T: ToString>(trait_type: &str, value: T, display_type: Option<&str>) -> Trait {
+    Trait {
+        display_type: display_type.map(String::from),
+        trait_type: Some(trait_type.to_string()),
+        value: value.to_string(),
+    }
+}
+
+fn query_last_fed<S: Storage>(storage: &S, account: &HumanAddr, pet_id: u64) -> QueryResult {
+    let pet = load_pet(storage, pet_id)?;
+    check_if_owner(&pet, account)?;
+    to_binary(&QueryResponse::LastFedResponse {
+        timestamp: pet.last_fed,
+    })
+}
+fn query_accepted_token<S: Storage>(storage: &S) -> QueryResult {
+    let state = config_read(storage).load()?;
+    to_binary(&QueryResponse::AcceptedToken {
+        address: state.accepted_token.address,
+        hash: state.accepted_token.hash,
+    })
+}
+fn query_food_tokens<S: Storage>(storage: &S) -> QueryResult {
+    let state = config_read(storage).load()?;
+    let food_tokens = state
+        .food_tokens
+        .into_iter()
+        .map(|food| FoodTokenInfo {
+            address: food.token.address,
+            hash: food.token.hash,
+            nutrition: food.nutrition,
+            effects: food.effects,
+        })
+        .collect();
+    to_binary(&QueryResponse::FoodTokens { food_tokens })
+}
+fn query_feeding_game<S: Storage>(storage: &S) -> QueryResult {
+    let state = config_read(storage).load()?;
+    to_binary(&QueryResponse::FeedingGame {
+        feeding_game: state.feeding_game,
+    })
+}
+fn query_contract_status<S: Storage>(storage: &S) -> QueryResult {
+    let state = config_read(storage).load()?;
+    to_binary(&QueryResponse::ContractStatus {
+        status: state.contract_status,
+    })
+}
+fn query_owner_record<S: Storage>(storage: &S, owner: &HumanAddr) -> QueryResult {
+    let record = load_owner_record(storage, owner)?;
+    to_binary(&QueryResponse::OwnerRecord {
+        feeding_streak: record.feeding_streak,
+        best_feeding_streak: record.best_feeding_streak,
+        care_score: record.care_score,
+        leaderboard_opt_out: record.leaderboard_opt_out,
+    })
+}
+fn query_pet_info<S: Storage>(storage: &S, account: &HumanAddr, pet_id: u64) -> QueryResult {
+    let pet = load_pet(storage, pet_id)?;
+    check_if_owner(&pet, account)?;
+    to_binary(&QueryResponse::PetInfoResponse {
+        id: pet.id,
+        name: pet.name,
+        description: pet.description,
+        image_uri: pet.image_uri,
+        owner: pet.owner,
+        born_at: pet.adopted_at,
+        stage: pet.stage,
+        allowed_feed_timespan: pet.allowed_feed_timespan,
+        total_saturation_time: pet.total_saturation_time,
+        caretakers: pet.caretakers,
+        stats: pet.stats,
+        stat_decay: pet.stat_decay,
+        deaths: pet.deaths,
+        died_at: pet.died_at,
+        feeding_streak: pet.feeding_streak,
+        saturation_penalty: pet.saturation_penalty,
+        achievements: pet.achievements,
+        generation: pet.generation,
+        parents: pet.parents,
+        bred_at: pet.bred_at,
+        breeding_partner: pet.breeding_partner.map(|partner| partner.pet_id),
+    })
+}
+fn query_pet_status<S: Storage>(
+    storage: &S,
+    account: &HumanAddr,
+    pet_id: u64,
+    current_time: u64,
+) -> QueryResult {
+    let state = config_read(storage).load()?;
+    let mut pet = load_pet(storage, pet_id)?;
+    check_if_owner(&pet, account)?;
+    let current_time = state.pet_time(current_time);
+    // a sleeping pet stands still until it wakes up
+    let sleep_left = pet.sleep_left(current_time);
+    let awake_time = current_time + sleep_left;
+    pet.evolve(awake_time, &state.stages);
+    to_binary(&QueryResponse::PetStatusResponse {
+        hunger_percentage: pet.hunger_percentage(awake_time),
+        seconds_until_feedable: pet.seconds_until_feedable(awake_time) + sleep_left,
+        seconds_until_death: pet.seconds_until_death(awake_time) + sleep_left,
+        state: pet.life_state(current_time),
+        stage: pet.stage,
+        age: awake_time.saturating_sub(pet.born_at),
+        stats: pet.stats_at(awake_time),
+        sick: pet.is_sick_at(awake_time),
+        seconds_until_awake: sleep_left,
+    })
+}
+fn query_pets<S: Storage>(storage: &S, owner: &HumanAddr) -> QueryResult {
+    let pet_ids = read_owned_pets(storage, owner)?;
+    to_binary(&QueryResponse::PetsResponse { pet_ids })
+}
+fn query_pet_history<S: Storage>(
+    storage: &S,
+    account: &HumanAddr,
+    pet_id: u64,
+    page: u32,
+    page_size: u32,
+) -> QueryResult {
+    // make sure the pet exists, a missing history would just look empty
+    let pet = load_pet(storage, pet_id)?;
+    check_if_owner(&pet, account)?;
+    let (events, total) = get_pet_history(storage, pet_id, page, page_size)?;
+    to_binary(&QueryResponse::PetHistory { events, total })
+}
+fn query_graveyard<S: Storage>(storage: &S, page: u32, page_size: u32) -> QueryResult {
+    let (graves, total) = get_graves(storage, page, page_size)?;
+    to_binary(&QueryResponse::Graveyard { graves, total })
+}
+// The leaderboards read their own indexes, which are kept up to date by the handle functions
+fn query_oldest_pets<S: Storage>(storage: &S, page: u32, page_size: u32) -> QueryResult {
+    let (pet_ids, total) = get_ranked_pets(storage, page, page_size)?;
+    let mut pets = vec![];
+    for pet_id in pet_ids {
+        let pet = load_pet(storage, pet_id)?;
+        pets.push(LeaderboardPet {
+            pet_id: pet.id,
+            name: pet.name,
+            owner: pet.owner,
+            born_at: pet.adopted_at,
+        });
+    }
+    to_binary(&QueryResponse::OldestPets { pets, total })
+}
+fn query_best_caretakers<S: Storage>(storage: &S, page: u32, page_size: u32) -> QueryResult {
+    let ranking = read_caretaker_ranking(storage)?;
+    let total = ranking.len() as u64;
+    let owners = ranking
+        .into_iter()
+        .skip((page as usize).saturating_mul(page_size as usize))
+        .take(page_size as _)
+        .collect();
+    to_binary(&QueryResponse::BestCaretakers { owners, total })
+}
+fn query_stages<S: Storage>(storage: &S) -> QueryResult {
+    let state = config_read(storage).load()?;
+    to_binary(&QueryResponse::StagesResponse {
+        stages: state.stages,
+    })
+}
+fn query_adoption_config<S: Storage>(storage: &S) -> QueryResult {
+    let state = config_read(storage).load()?;
+    to_binary(&QueryResponse::AdoptionConfig {
+        adoption_price: state.adoption_price,
+        max_pets_per_owner: state.max_pets_per_owner,
+    })
+}
+fn query_revive_config<S: Storage>(storage: &S) -> QueryResult {
+    let state = config_read(storage).load()?;
+    to_binary(&QueryResponse::ReviveConfig {
+        revive_price: state.revive_price,
+        revive_price_increase: state.revive_price_increase,
+    })
+}
+fn query_sleep_config<S: Storage>(storage: &S) -> QueryResult {
+    let state = config_read(storage).load()?;
+    to_binary(&QueryResponse::SleepConfig {
+        sleep_price: state.sleep_price,
+        max_sleep_duration: state.max_sleep_duration,
+        sleep_cooldown: state.sleep_cooldown,
+    })
+}
+fn query_breeding_config<S: Storage>(storage: &S) -> QueryResult {
+    let state = config_read(storage).load()?;
+    to_binary(&QueryResponse::BreedingConfig {
+        breed_price: state.breed_price,
+        breeding_cooldown: state.breeding_cooldown,
+    })
+}
+fn query_name_config<S: Storage>(storage: &S) -> QueryResult {
+    let state = config_read(storage).load()?;
+    to_binary(&QueryResponse::NameConfig {
+        rename_price: state.rename_price,
+        unique_names: state.unique_names,
+    })
+}
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        from_binary,
+        testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage},
+        to_binary, Binary, Extern, HumanAddr, StdError, Uint128,
+    };
+
+    use crate::{
+        msg::{
+            AccessLevel, ContractStatusLevel, HandleAnswer, HandleMsg, InitMsg, NftQueryAnswer,
+            QueryMsg, QueryResponse, ReceiveMsg, ResponseStatus, Send, Transfer, ViewerInfo,
+        },
+        pet_history::PetEvent,
+        state::{
+            Achievement, CareAction, Expiration, FeedingGame, FoodToken, LifeState, SecretToken,
+            Stage, StageConfig, StatEffect, StatKind,
+        },
+    };
+
+    use super::{handle, init, query};
+
+    fn init_default() -> Extern<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies(20, &[]);
+        let env = mock_env("sender", &[]);
+        let msg = InitMsg {
+            accepted_token: SecretToken {
+                address: HumanAddr::from("food"),
+                hash: "".to_string(),
+                viewing_key: "supersecret".to_string(),
+            },
+            admin: None,
+            allowed_feed_timespan: 3600,
+            total_saturation_time: 14200,
+            saturation_per_food: 36,
+            refund_excess_food: true,
+            stages: None,
+            adoption_price: None,
+            max_pets_per_owner: None,
+            stat_decay: None,
+            revive_price: None,
+            revive_price_increase: None,
+            prng_seed: Binary::from("lolz fun yay".as_bytes()),
+        };
+
+        let _res = init(&mut deps, env, msg).unwrap();
+        deps
+    }
+
+    fn receive_msg(from: &str, amount: u128, msg: &ReceiveMsg) -> HandleMsg {
+        HandleMsg::Receive {
+            sender: HumanAddr::from(from),
+            from: HumanAddr::from(from),
+            amount: Uint128(amount),
+            msg: Some(to_binary(msg).unwrap()),
+        }
+    }
+
+    fn adopt(deps: &mut Extern<MockStorage, MockApi, MockQuerier>, owner: &str) {
+        let msg = HandleMsg::Adopt {
+            name: "Foxy".to_string(),
+            description: None,
+            image_uri: None,
+        };
+        handle(deps, mock_env(owner, &[]), msg).unwrap();
+        set_key(deps, owner);
+    }
+
+    fn set_key(deps: &mut Extern<MockStorage, MockApi, MockQuerier>, owner: &str) {
+        let msg = HandleMsg::SetViewingKey {
+            key: "key".to_string(),
+        };
+        handle(deps, mock_env(owner, &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_init() {
+        let deps = init_default();
+
+        let res = query(&deps, QueryMsg::AdoptionConfig {}).unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::AdoptionConfig {
+                adoption_price,
+                max_pets_per_owner,
+            } => {
+                assert_eq!(adoption_price, None);
+                assert_eq!(max_pets_per_owner, None);
+            }
+            _ => panic!("unexpected query response"),
+        }
+    }
+
+    #[test]
+    fn test_adopt() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        adopt(&mut deps, "alice");
+
+        let res = query(&deps, QueryMsg::PetSummary { pet_id: 1 }).unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::PetSummary {
+                id, name, owner, ..
+            } => {
+                assert_eq!(id, 1);
+                assert_eq!(name, "Foxy");
+                assert_eq!(owner, HumanAddr::from("alice"));
+            }
+            _ => panic!("unexpected query response"),
+        }
+
+        let res = query(
+            &deps,
+            QueryMsg::Pets {
+                owner: HumanAddr::from("alice"),
+            },
+        )
+        .unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::PetsResponse { pet_ids } => assert_eq!(pet_ids, vec![0, 1]),
+            _ => panic!("unexpected query response"),
+        }
+    }
+
+    #[test]
+    fn test_adoption_config() {
+        let mut deps = init_default();
+
+        let msg = HandleMsg::SetAdoptionConfig {
+            adoption_price: Some(Uint128(50)),
+            max_pets_per_owner: Some(1),
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), msg.clone());
+        assert!(res.is_err(), "only the admin can change the config");
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+
+        let msg = HandleMsg::Adopt {
+            name: "Foxy".to_string(),
+            description: None,
+            image_uri: None,
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), msg);
+        assert!(res.is_err(), "adoption should require payment");
+
+        let adopt_msg = ReceiveMsg::Adopt {
+            name: "Foxy".to_string(),
+            description: None,
+            image_uri: None,
+        };
+        let res = handle(
+            &mut deps,
+            mock_env("food", &[]),
+            receive_msg("alice", 49, &adopt_msg),
+        );
+        assert!(res.is_err(), "underpaying should fail");
+        let res = handle(
+            &mut deps,
+            mock_env("food", &[]),
+            receive_msg("alice", 50, &adopt_msg),
+        );
+        assert_eq!(res.unwrap().messages.len(), 1);
+
+        match handle(
+            &mut deps,
+            mock_env("food", &[]),
+            receive_msg("alice", 50, &adopt_msg),
+        ) {
+            Err(StdError::GenericErr { msg, .. }) => assert!(msg.contains("living pets")),
+            _ => panic!("adopting over the limit should fail"),
+        }
+
+        // an owner who reached the limit can't be given more living pets either
+        let res = handle(
+            &mut deps,
+            mock_env("food", &[]),
+            receive_msg("bob", 50, &adopt_msg),
+        );
+        assert!(res.is_ok(), "{:?}", res.err());
+        let msg = HandleMsg::TransferPet {
+            pet_id: 0,
+            recipient: HumanAddr::from("bob"),
+        };
+        match handle(&mut deps, mock_env("alice", &[]), msg) {
+            Err(StdError::GenericErr { msg, .. }) => assert!(msg.contains("living pets")),
+            _ => panic!("transferring over the limit should fail"),
+        }
+        let msg = HandleMsg::TransferNft {
+            recipient: HumanAddr::from("bob"),
+            token_id: "0".to_string(),
+            memo: None,
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), msg);
+        assert!(res.is_err(), "the SNIP-721 transfer is limited too");
+        let msg = HandleMsg::TransferPet {
+            pet_id: 0,
+            recipient: HumanAddr::from("carol"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_transfer_and_caretakers() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let mut env = mock_env("food", &[]);
+        env.block.time += 3601;
+        let feed = ReceiveMsg::Feed { pet_id: 0 };
+
+        let res = handle(&mut deps, env.clone(), receive_msg("bob", 100, &feed));
+        assert!(res.is_err(), "strangers can't feed the pet");
+
+        let msg = HandleMsg::AddCaretaker {
+            pet_id: 0,
+            caretaker: HumanAddr::from("bob"),
+            expiration: Some(env.block.time + 10),
+        };
+        let res = handle(&mut deps, mock_env("bob", &[]), msg.clone());
+        assert!(res.is_err(), "only the owner can add caretakers");
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        handle(&mut deps, env.clone(), receive_msg("bob", 100, &feed)).unwrap();
+
+        let msg = HandleMsg::TransferPet {
+            pet_id: 0,
+            recipient: HumanAddr::from("carol"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+
+        set_key(&mut deps, "carol");
+        let res = query(
+            &deps,
+            QueryMsg::PetInfo {
+                pet_id: 0,
+                address: HumanAddr::from("carol"),
+                key: "key".to_string(),
+            },
+        )
+        .unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::PetInfoResponse {
+                owner, caretakers, ..
+            } => {
+                assert_eq!(owner, HumanAddr::from("carol"));
+                assert!(caretakers.is_empty());
+            }
+            _ => panic!("unexpected query response"),
+        }
+        let res = query(
+            &deps,
+            QueryMsg::Pets {
+                owner: HumanAddr::from("alice"),
+            },
+        )
+        .unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::PetsResponse { pet_ids } => assert!(pet_ids.is_empty()),
+            _ => panic!("unexpected query response"),
+        }
+    }
+
+    #[test]
+    fn test_feed_amount() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let mut env = mock_env("food", &[]);
+        env.block.time += 7200;
+        let feed = ReceiveMsg::Feed { pet_id: 0 };
+
+        // 50 food is only 1800 seconds of saturation, the pet stays hungry
+        let res = handle(&mut deps, env.clone(), receive_msg("alice", 50, &feed)).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        // the pet can only eat 150 more, the rest is refunded
+        env.block.time += 1;
+        let res = handle(&mut deps, env.clone(), receive_msg("alice", 1000, &feed)).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert!(res
+            .log
+            .iter()
+            .any(|l| l.key == "food_refunded" && l.value == "849"));
+
+        let res = query(
+            &deps,
+            QueryMsg::LastFed {
+                pet_id: 0,
+                address: HumanAddr::from("alice"),
+                key: "key".to_string(),
+            },
+        )
+        .unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::LastFedResponse { timestamp } => assert_eq!(timestamp, env.block.time),
+            _ => panic!("unexpected query response"),
+        }
+    }
+
+    #[test]
+    fn test_pet_status() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let born = mock_env("alice", &[]).block.time;
+
+        let status = |time: u64| {
+            let msg = QueryMsg::PetStatus {
+                address: HumanAddr::from("alice"),
+                key: "key".to_string(),
+                pet_id: 0,
+                current_time: time,
+            };
+            match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+                QueryResponse::PetStatusResponse {
+                    hunger_percentage,
+                    seconds_until_feedable,
+                    seconds_until_death,
+                    state,
+                    ..
+                } => (
+                    hunger_percentage,
+                    seconds_until_feedable,
+                    seconds_until_death,
+                    state,
+                ),
+                _ => panic!("unexpected query response"),
+            }
+        };
+
+        assert_eq!(status(born), (0, 3601, 14200, LifeState::Full));
+        assert_eq!(status(born + 7100), (50, 0, 7100, LifeState::Hungry));
+        assert_eq!(status(born + 11000), (77, 0, 3200, LifeState::Starving));
+        assert_eq!(status(born + 14201), (100, 0, 0, LifeState::Dead));
+    }
+
+    #[test]
+    fn test_evolution() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let mut env = mock_env("food", &[]);
+        env.block.time += 3601;
+
+        let msg = QueryMsg::PetStatus {
+            address: HumanAddr::from("alice"),
+            key: "key".to_string(),
+            pet_id: 0,
+            current_time: env.block.time,
+        };
+        match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            QueryResponse::PetStatusResponse { stage, age, .. } => {
+                assert_eq!(stage, Stage::Baby);
+                assert_eq!(age, 3601);
+            }
+            _ => panic!("unexpected query response"),
+        }
+
+        let feed = ReceiveMsg::Feed { pet_id: 0 };
+        let res = handle(&mut deps, env, receive_msg("alice", 100, &feed)).unwrap();
+        assert!(res
+            .log
+            .iter()
+            .any(|l| l.key == "evolved_to" && l.value == "baby"));
+    }
+
+    #[test]
+    fn test_feed_routes_to_pet() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let mut env = mock_env("food", &[]);
+        env.block.time += 3601;
+
+        let msg = receive_msg("alice", 100, &ReceiveMsg::Feed { pet_id: 0 });
+        handle(&mut deps, env.clone(), msg).unwrap();
+
+        let res = query(
+            &deps,
+            QueryMsg::LastFed {
+                pet_id: 0,
+                address: HumanAddr::from("alice"),
+                key: "key".to_string(),
+            },
+        )
+        .unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::LastFedResponse { timestamp } => {
+                assert_eq!(timestamp, env.block.time - 1)
+            }
+            _ => panic!("unexpected query response"),
+        }
+
+        let msg = receive_msg("alice", 100, &ReceiveMsg::Feed { pet_id: 7 });
+        match handle(&mut deps, env, msg) {
+            Err(StdError::GenericErr { msg, .. }) => assert!(msg.contains("does not exist")),
+            _ => panic!("feeding a missing pet should fail"),
+        }
+    }
+
+    #[test]
+    fn test_care() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let mut env = mock_env("food", &[]);
+        let feed = ReceiveMsg::Feed { pet_id: 0 };
+
+        let status = |deps: &Extern<MockStorage, MockApi, MockQuerier>, time: u64| {
+            let msg = QueryMsg::PetStatus {
+                address: HumanAddr::from("alice"),
+                key: "key".to_string(),
+                pet_id: 0,
+                current_time: time,
+            };
+            match from_binary(&query(deps, msg).unwrap()).unwrap() {
+                QueryResponse::PetStatusResponse { stats, sick, .. } => (stats, sick),
+                _ => panic!("unexpected query response"),
+            }
+        };
+
+        // well fed, but never played with, cleaned or rested for 26 hours
+        for _ in 0..26 {
+            env.block.time += 3601;
+            handle(&mut deps, env.clone(), receive_msg("alice", 100, &feed)).unwrap();
+        }
+        let (stats, sick) = status(&deps, env.block.time);
+        assert_eq!(stats.happiness.value, 0);
+        assert_eq!(stats.energy.value, 0);
+        assert_eq!(stats.health.value, 74);
+        assert!(sick, "a pet neglected in two ways gets sick");
+
+        let msg = HandleMsg::Care {
+            pet_id: 0,
+            action: CareAction::Play,
+        };
+        let res = handle(&mut deps, mock_env("bob", &[]), msg.clone());
+        assert!(res.is_err(), "strangers can't take care of the pet");
+        let mut alice = mock_env("alice", &[]);
+        alice.block.time = env.block.time;
+        handle(&mut deps, alice.clone(), msg).unwrap();
+
+        let (stats, sick) = status(&deps, env.block.time);
+        assert_eq!(stats.happiness.value, 100);
+        assert!(!sick);
+
+        // without medicine the health runs out after 100 hours even if the pet is fed
+        for _ in 0..74 {
+            env.block.time += 3601;
+            let _res = handle(&mut deps, env.clone(), receive_msg("alice", 100, &feed));
+        }
+        let (_, sick) = status(&deps, env.block.time);
+        assert!(sick);
+        match handle(&mut deps, env, receive_msg("alice", 100, &feed)) {
+            Err(StdError::GenericErr { msg, .. }) => assert!(msg.contains("dead")),
+            _ => panic!("the pet should have died of illness"),
+        }
+
+        let res = query(
+            &deps,
+            QueryMsg::PetInfo {
+                pet_id: 0,
+                address: HumanAddr::from("alice"),
+                key: "key".to_string(),
+            },
+        )
+        .unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::PetInfoResponse {
+                stats, stat_decay, ..
+            } => {
+                assert_eq!(stats.happiness.updated_at, alice.block.time);
+                assert_eq!(stat_decay.health, 1);
+            }
+            _ => panic!("unexpected query response"),
+        }
+    }
+
+    #[test]
+    fn test_revive() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let mut env = mock_env("alice", &[]);
+        let revive = HandleMsg::Revive { pet_id: 0 };
+
+        let res = handle(&mut deps, env.clone(), revive.clone());
+        assert!(res.is_err(), "a living pet can't be revived");
+
+        env.block.time += 14201;
+        let res = handle(&mut deps, mock_env("bob", &[]), revive.clone());
+        assert!(res.is_err(), "only the owner can revive the pet");
+        handle(&mut deps, env.clone(), revive).unwrap();
+
+        let msg = HandleMsg::SetReviveConfig {
+            revive_price: Some(Uint128(100)),
+            revive_price_increase: Uint128(50),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+
+        env.block.time += 14201;
+        let mut food = mock_env("food", &[]);
+        food.block.time = env.block.time;
+        let revive = ReceiveMsg::Revive { pet_id: 0 };
+        let res = handle(&mut deps, food.clone(), receive_msg("alice", 100, &revive));
+        assert!(res.is_err(), "the price escalates with every death");
+        let res = handle(&mut deps, food.clone(), receive_msg("alice", 150, &revive)).unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        let res = query(
+            &deps,
+            QueryMsg::PetInfo {
+                pet_id: 0,
+                address: HumanAddr::from("alice"),
+                key: "key".to_string(),
+            },
+        )
+        .unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::PetInfoResponse { deaths, .. } => assert_eq!(deaths, 2),
+            _ => panic!("unexpected query response"),
+        }
+        let res = query(
+            &deps,
+            QueryMsg::LastFed {
+                pet_id: 0,
+                address: HumanAddr::from("alice"),
+                key: "key".to_string(),
+            },
+        )
+        .unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::LastFedResponse { timestamp } => assert_eq!(timestamp, food.block.time),
+            _ => panic!("unexpected query response"),
+        }
+
+        let msg = HandleMsg::SetReviveConfig {
+            revive_price: Some(Uint128(100)),
+            revive_price_increase: Uint128(u128::MAX),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        food.block.time += 14201;
+        let res = handle(&mut deps, food, receive_msg("alice", 100, &revive));
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => assert!(msg.contains("overflows")),
+            _ => panic!("an overflowing revive price is rejected"),
+        }
+    }
+    #[test]
+    fn test_history_and_graveyard() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let mut env = mock_env("food", &[]);
+        env.block.time += 3601;
+        let feed = ReceiveMsg::Feed { pet_id: 0 };
+        handle(&mut deps, env.clone(), receive_msg("alice", 100, &feed)).unwrap();
+
+        let msg = HandleMsg::Bury { pet_id: 0 };
+        let res = handle(&mut deps, env.clone(), msg.clone());
+        assert!(res.is_err(), "a living pet can't be buried");
+        let mut bob = mock_env("bob", &[]);
+        bob.block.time = env.block.time + 20000;
+        handle(&mut deps, bob.clone(), msg.clone()).unwrap();
+        let res = handle(&mut deps, bob.clone(), msg);
+        assert!(res.is_err(), "a pet is only buried once");
+
+        let mut alice = mock_env("alice", &[]);
+        alice.block.time = bob.block.time;
+        handle(&mut deps, alice, HandleMsg::Revive { pet_id: 0 }).unwrap();
+
+        let msg = QueryMsg::PetHistory {
+            address: HumanAddr::from("alice"),
+            key: "key".to_string(),
+            pet_id: 0,
+            page: None,
+            page_size: 10,
+        };
+        match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            QueryResponse::PetHistory { events, total } => {
+                assert_eq!(total, 5);
+                let events: Vec<PetEvent> = events.into_iter().map(|e| e.event).collect();
+                assert_eq!(
+                    events,
+                    vec![
+                        PetEvent::Revived { deaths: 1 },
+                        PetEvent::Died {
+                            died_at: env.block.time - 1 + 14201
+                        },
+                        PetEvent::Fed {
+                            feeder: HumanAddr::from("alice"),
+                            food_eaten: Uint128(100),
+                        },
+                        PetEvent::Evolved { stage: Stage::Baby },
+                        PetEvent::Born {
+                            owner: HumanAddr::from("alice")
+                        },
+                    ]
+                );
+            }
+            _ => panic!("unexpected query response"),
+        }
+
+        let msg = QueryMsg::Graveyard {
+            page: Some(0),
+            page_size: 10,
+        };
+        match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            QueryResponse::Graveyard { graves, total } => {
+                assert_eq!(total, 1);
+                assert_eq!(graves[0].pet_id, 0);
+                assert_eq!(graves[0].died_at, env.block.time - 1 + 14201);
+            }
+            _ => panic!("unexpected query response"),
+        }
+        // a page far past the end is empty instead of overflowing
+        let msg = QueryMsg::Graveyard {
+            page: Some(u32::MAX),
+            page_size: u32::MAX,
+        };
+        match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            QueryResponse::Graveyard { graves, total } => {
+                assert!(graves.is_empty());
+                assert_eq!(total, 1);
+            }
+            _ => panic!("unexpected query response"),
+        }
+    }
+    #[test]
+    fn test_admin_config() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+
+        let msg = HandleMsg::UpdateConfig {
+            allowed_feed_timespan: Some(60),
+            total_saturation_time: Some(600),
+            stages: None,
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), msg.clone());
+        assert!(res.is_err(), "only the admin can update the config");
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+
+        // existing pets pick up the new timespans
+        let msg = QueryMsg::PetStatus {
+            address: HumanAddr::from("alice"),
+            key: "key".to_string(),
+            pet_id: 0,
+            current_time: mock_env("alice", &[]).block.time + 601,
+        };
+        match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            QueryResponse::PetStatusResponse { state, .. } => assert_eq!(state, LifeState::Dead),
+            _ => panic!("unexpected query response"),
+        }
+
+        let msg = HandleMsg::ChangeAdmin {
+            address: HumanAddr::from("alice"),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+
+        let msg = HandleMsg::SetAcceptedToken {
+            accepted_token: SecretToken {
+                address: HumanAddr::from("food2"),
+                hash: "".to_string(),
+                viewing_key: "supersecret".to_string(),
+            },
+        };
+        let res = handle(&mut deps, mock_env("sender", &[]), msg.clone());
+        assert!(res.is_err(), "the old admin can't change the token");
+        let res = handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 2);
+
+        let res = query(&deps, QueryMsg::AcceptedToken {}).unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::AcceptedToken { address, .. } => {
+                assert_eq!(address, HumanAddr::from("food2"))
+            }
+            _ => panic!("unexpected query response"),
+        }
+    }
+
+    #[test]
+    fn test_contract_status() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+
+        let msg = HandleMsg::SetContractStatus {
+            level: ContractStatusLevel::StopAllButFeeding,
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+
+        let msg = HandleMsg::Adopt {
+            name: "Foxy".to_string(),
+            description: None,
+            image_uri: None,
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), msg);
+        assert!(res.is_err(), "adopting is stopped");
+
+        let mut env = mock_env("food", &[]);
+        env.block.time += 3601;
+        let feed = ReceiveMsg::Feed { pet_id: 0 };
+        handle(&mut deps, env.clone(), receive_msg("alice", 100, &feed)).unwrap();
+
+        let msg = HandleMsg::SetContractStatus {
+            level: ContractStatusLevel::StopAll,
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        env.block.time += 3601;
+        let res = handle(&mut deps, env, receive_msg("alice", 100, &feed));
+        assert!(res.is_err(), "feeding is stopped");
+
+        let res = query(&deps, QueryMsg::ContractStatus {}).unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::ContractStatus { status } => {
+                assert_eq!(status, ContractStatusLevel::StopAll)
+            }
+            _ => panic!("unexpected query response"),
+        }
+    }
+    #[test]
+    fn test_paused_clock() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let mut env = mock_env("sender", &[]);
+        let born = env.block.time;
+
+        env.block.time = born + 1000;
+        let msg = HandleMsg::SetContractStatus {
+            level: ContractStatusLevel::StopAll,
+        };
+        handle(&mut deps, env.clone(), msg).unwrap();
+
+        let status = |deps: &Extern<MockStorage, MockApi, MockQuerier>, time: u64| {
+            let msg = QueryMsg::PetStatus {
+                address: HumanAddr::from("alice"),
+                key: "key".to_string(),
+                pet_id: 0,
+                current_time: time,
+            };
+            match from_binary(&query(deps, msg).unwrap()).unwrap() {
+                QueryResponse::PetStatusResponse {
+                    seconds_until_death,
+                    state,
+                    ..
+                } => (seconds_until_death, state),
+                _ => panic!("unexpected query response"),
+            }
+        };
+        // the pet doesn't starve while the contract is paused
+        assert_eq!(status(&deps, born + 50000), (13200, LifeState::Full));
+
+        env.block.time = born + 50000;
+        let msg = HandleMsg::SetContractStatus {
+            level: ContractStatusLevel::NormalRun,
+        };
+        handle(&mut deps, env.clone(), msg).unwrap();
+        assert_eq!(status(&deps, born + 50000), (13200, LifeState::Full));
+
+        let mut food = mock_env("food", &[]);
+        food.block.time = born + 50000 + 2601;
+        let feed = ReceiveMsg::Feed { pet_id: 0 };
+        let res = handle(&mut deps, food.clone(), receive_msg("alice", 100, &feed)).unwrap();
+        assert!(res
+            .log
+            .iter()
+            .any(|l| l.key == "food_eaten" && l.value == "100"));
+    }
+    #[test]
+    fn test_sleep() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let mut env = mock_env("alice", &[]);
+        let born = env.block.time;
+
+        env.block.time = born + 1000;
+        let msg = HandleMsg::Sleep {
+            pet_id: 0,
+            duration: 100000,
+        };
+        handle(&mut deps, env.clone(), msg).unwrap();
+
+        let status = |deps: &Extern<MockStorage, MockApi, MockQuerier>, time: u64| {
+            let msg = QueryMsg::PetStatus {
+                address: HumanAddr::from("alice"),
+                key: "key".to_string(),
+                pet_id: 0,
+                current_time: time,
+            };
+            match from_binary(&query(deps, msg).unwrap()).unwrap() {
+                QueryResponse::PetStatusResponse {
+                    seconds_until_death,
+                    state,
+                    age,
+                    ..
+                } => (seconds_until_death, state, age),
+                _ => panic!("unexpected query response"),
+            }
+        };
+        assert_eq!(
+            status(&deps, born + 51000),
+            (63200, LifeState::Asleep, 1000)
+        );
+
+        let mut food = mock_env("food", &[]);
+        food.block.time = born + 51000;
+        let feed = ReceiveMsg::Feed { pet_id: 0 };
+        let res = handle(&mut deps, food, receive_msg("alice", 100, &feed));
+        assert!(res.is_err(), "a sleeping pet can't be fed");
+
+        env.block.time = born + 51000;
+        handle(&mut deps, env.clone(), HandleMsg::Wake { pet_id: 0 }).unwrap();
+        assert_eq!(status(&deps, born + 51000), (13200, LifeState::Full, 1000));
+
+        let msg = HandleMsg::Sleep {
+            pet_id: 0,
+            duration: 100,
+        };
+        let res = handle(&mut deps, env, msg);
+        assert!(res.is_err(), "the pet has to wait for the cooldown");
+    }
+    #[test]
+    fn test_food_tokens() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+
+        let msg = HandleMsg::AddFoodToken {
+            food_token: FoodToken {
+                token: SecretToken {
+                    address: HumanAddr::from("treat"),
+                    hash: "".to_string(),
+                    viewing_key: "supersecret".to_string(),
+                },
+                nutrition: 200,
+                effects: vec![StatEffect {
+                    stat: StatKind::Happiness,
+                    points: 5,
+                }],
+            },
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), msg.clone());
+        assert!(res.is_err(), "only the admin can add food tokens");
+        if let HandleMsg::AddFoodToken { mut food_token } = msg.clone() {
+            food_token.nutrition = u64::MAX;
+            let msg = HandleMsg::AddFoodToken { food_token };
+            let res = handle(&mut deps, mock_env("sender", &[]), msg);
+            assert!(res.is_err(), "nutrition is capped");
+        }
+        let res = handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 2);
+
+        // play first, so happiness decays from a known value
+        let mut alice = mock_env("alice", &[]);
+        alice.block.time += 3601;
+        let msg = HandleMsg::Care {
+            pet_id: 0,
+            action: CareAction::Play,
+        };
+        handle(&mut deps, alice.clone(), msg).unwrap();
+
+        let mut treat = mock_env("treat", &[]);
+        treat.block.time += 3601 + 9000;
+        let feed = ReceiveMsg::Feed { pet_id: 0 };
+        // a treat gives 72 seconds of saturation, 12601 seconds need 176 treats
+        let res = handle(&mut deps, treat.clone(), receive_msg("alice", 200, &feed)).unwrap();
+        assert!(res
+            .log
+            .iter()
+            .any(|l| l.key == "food_eaten" && l.value == "176"));
+
+        let msg = QueryMsg::PetStatus {
+            address: HumanAddr::from("alice"),
+            key: "key".to_string(),
+            pet_id: 0,
+            current_time: treat.block.time,
+        };
+        match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            QueryResponse::PetStatusResponse { stats, .. } => {
+                // 90 after two and a half hours, raised by the treat
+                assert_eq!(stats.happiness.value, 95)
+            }
+            _ => panic!("unexpected query response"),
+        }
+
+        let adopt_msg = ReceiveMsg::Adopt {
+            name: "Foxy".to_string(),
+            description: None,
+            image_uri: None,
+        };
+        let res = handle(&mut deps, treat, receive_msg("alice", 50, &adopt_msg));
+        assert!(res.is_err(), "treats can't be used to pay");
+    }
+    #[test]
+    fn test_feeding_game() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let msg = HandleMsg::SetFeedingGame {
+            feeding_game: Some(FeedingGame {
+                overfeeding_health_penalty: 20,
+                overfeeding_saturation_penalty: 1000,
+                bonus_window: 600,
+                bonus_happiness: 10,
+            }),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+
+        let info = |deps: &Extern<MockStorage, MockApi, MockQuerier>| match from_binary(
+            &query(
+                deps,
+                QueryMsg::PetInfo {
+                    pet_id: 0,
+                    address: HumanAddr::from("alice"),
+                    key: "key".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            QueryResponse::PetInfoResponse {
+                total_saturation_time,
+                feeding_streak,
+                saturation_penalty,
+                stats,
+                ..
+            } => (
+                total_saturation_time,
+                feeding_streak,
+                saturation_penalty,
+                stats.health.value,
+            ),
+            _ => panic!("unexpected query response"),
+        };
+
+        let mut env = mock_env("food", &[]);
+        let feed = ReceiveMsg::Feed { pet_id: 0 };
+        // too early, the pet is overfed
+        env.block.time += 1800;
+        let res = handle(&mut deps, env.clone(), receive_msg("alice", 100, &feed)).unwrap();
+        assert!(res
+            .log
+            .iter()
+            .any(|l| l.key == "overfed" && l.value == "true"));
+        assert_eq!(info(&deps), (13200, 0, 1000, 80));
+
+        // right when the window opens, the streak starts and the penalty is taken back
+        env.block.time += 3601;
+        handle(&mut deps, env.clone(), receive_msg("alice", 200, &feed)).unwrap();
+        assert_eq!(info(&deps), (14200, 1, 0, 80));
+        // a full pet eats nothing, so feeding it again in the same block doesn't count
+        let res = handle(&mut deps, env.clone(), receive_msg("alice", 200, &feed));
+        assert!(res.is_err(), "the pet is full");
+        assert_eq!(info(&deps), (14200, 1, 0, 80));
+
+        // late in the window, the streak is lost
+        env.block.time += 7000;
+        handle(&mut deps, env, receive_msg("alice", 200, &feed)).unwrap();
+        assert_eq!(info(&deps).1, 0);
+    }
+    #[test]
+    fn test_achievements() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let owner_record = |deps: &Extern<MockStorage, MockApi, MockQuerier>| {
+            let msg = QueryMsg::OwnerRecord {
+                address: HumanAddr::from("alice"),
+                key: "key".to_string(),
+            };
+            match from_binary(&query(deps, msg).unwrap()).unwrap() {
+                QueryResponse::OwnerRecord {
+                    feeding_streak,
+                    best_feeding_streak,
+                    care_score,
+                    ..
+                } => (feeding_streak, best_feeding_streak, care_score),
+                _ => panic!("unexpected query response"),
+            }
+        };
+        let achievements = |deps: &Extern<MockStorage, MockApi, MockQuerier>| match from_binary(
+            &query(
+                deps,
+                QueryMsg::PetInfo {
+                    pet_id: 0,
+                    address: HumanAddr::from("alice"),
+                    key: "key".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        {
+            QueryResponse::PetInfoResponse { achievements, .. } => achievements,
+            _ => panic!("unexpected query response"),
+        };
+
+        let mut env = mock_env("food", &[]);
+        let feed = ReceiveMsg::Feed { pet_id: 0 };
+        env.block.time += 3601;
+        let res = handle(&mut deps, env.clone(), receive_msg("alice", 100, &feed)).unwrap();
+        assert!(res
+            .log
+            .iter()
+            .any(|l| l.key == "achievement" && l.value == "first_feed"));
+        assert_eq!(owner_record(&deps), (1, 1, 10));
+
+        // feeding on time for a week, with medicine so the health of the pet holds up
+        let streak_started_at = env.block.time;
+        let mut feedings = 1;
+        while env.block.time - streak_started_at < 604800 {
+            env.block.time += 3601;
+            let mut alice = mock_env("alice", &[]);
+            alice.block.time = env.block.time;
+            let care = HandleMsg::Care {
+                pet_id: 0,
+                action: CareAction::Medicine,
+            };
+            handle(&mut deps, alice, care).unwrap();
+            handle(&mut deps, env.clone(), receive_msg("alice", 100, &feed)).unwrap();
+            feedings += 1;
+        }
+        assert_eq!(
+            owner_record(&deps),
+            (feedings, feedings, feedings as u64 * 10)
+        );
+        assert_eq!(
+            achievements(&deps),
+            vec![
+                Achievement::FirstFeed,
+                Achievement::ReachedAdult,
+                Achievement::WeekStreak
+            ]
+        );
+
+        // a starving pet is fed late, which breaks the streaks
+        env.block.time += 11000;
+        handle(&mut deps, env.clone(), receive_msg("alice", 100, &feed)).unwrap();
+        assert_eq!(owner_record(&deps), (0, feedings, feedings as u64 * 10 + 2));
+
+        // the death of the pet costs care score, reviving it is an achievement
+        env.block.time += 14201;
+        let mut alice = mock_env("alice", &[]);
+        alice.block.time = env.block.time;
+        let res = handle(&mut deps, alice, HandleMsg::Revive { pet_id: 0 }).unwrap();
+        assert!(res
+            .log
+            .iter()
+            .any(|l| l.key == "achievement" && l.value == "revived"));
+        assert_eq!(owner_record(&deps).2, feedings as u64 * 10 + 2 - 50);
+        assert_eq!(achievements(&deps).len(), 4);
+    }
+    #[test]
+    fn test_leaderboards() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let mut env = mock_env("bob", &[]);
+        env.block.time += 100;
+        let msg = HandleMsg::Adopt {
+            name: "Rex".to_string(),
+            description: None,
+            image_uri: None,
+        };
+        handle(&mut deps, env.clone(), msg).unwrap();
+
+        let mut food = mock_env("food", &[]);
+        food.block.time += 3701;
+        let feed = ReceiveMsg::Feed { pet_id: 1 };
+        handle(&mut deps, food.clone(), receive_msg("bob", 100, &feed)).unwrap();
+
+        let born = mock_env("alice", &[]).block.time;
+        let oldest_pets = |deps: &Extern<MockStorage, MockApi, MockQuerier>, page| {
+            let msg = QueryMsg::OldestPets {
+                page: Some(page),
+                page_size: 1,
+            };
+            match from_binary(&query(deps, msg).unwrap()).unwrap() {
+                QueryResponse::OldestPets { pets, total } => (
+                    pets.iter()
+                        .map(|pet| (pet.pet_id, pet.born_at))
+                        .collect::<Vec<_>>(),
+                    total,
+                ),
+                _ => panic!("unexpected query response"),
+            }
+        };
+        let best_caretakers = |deps: &Extern<MockStorage, MockApi, MockQuerier>| {
+            let msg = QueryMsg::BestCaretakers {
+                page: None,
+                page_size: 10,
+            };
+            match from_binary(&query(deps, msg).unwrap()).unwrap() {
+                QueryResponse::BestCaretakers { owners, .. } => owners
+                    .into_iter()
+                    .map(|owner| (owner.owner, owner.care_score))
+                    .collect::<Vec<_>>(),
+                _ => panic!("unexpected query response"),
+            }
+        };
+
+        assert_eq!(oldest_pets(&deps, 0), (vec![(0, born)], 2));
+        assert_eq!(oldest_pets(&deps, 1), (vec![(1, born + 100)], 2));
+        assert_eq!(oldest_pets(&deps, u32::MAX), (vec![], 2));
+        // bob has a care score of 10, the leaderboard only shows it rounded down
+        assert_eq!(best_caretakers(&deps), vec![(HumanAddr::from("bob"), 0)]);
+
+        // sleeping doesn't change the birth time on the leaderboard
+        let mut sleep = mock_env("bob", &[]);
+        sleep.block.time = food.block.time;
+        let msg = HandleMsg::Sleep {
+            pet_id: 1,
+            duration: 1000,
+        };
+        handle(&mut deps, sleep, msg).unwrap();
+        assert_eq!(oldest_pets(&deps, 1), (vec![(1, born + 100)], 2));
+
+        // the pet of alice starves, it leaves the leaderboard with the first message after the
+        // hour it died in, before anyone buries it
+        let mut carol = mock_env("carol", &[]);
+        carol.block.time = born + 14201;
+        let msg = HandleMsg::CreateViewingKey {
+            entropy: "entropy".to_string(),
+        };
+        handle(&mut deps, carol.clone(), msg.clone()).unwrap();
+        assert_eq!(oldest_pets(&deps, 0), (vec![(0, born)], 2));
+        carol.block.time += 3600;
+        handle(&mut deps, carol.clone(), msg).unwrap();
+        assert_eq!(oldest_pets(&deps, 0), (vec![(1, born + 100)], 1));
+
+        let mut bury = mock_env("bob", &[]);
+        bury.block.time = carol.block.time;
+        handle(&mut deps, bury, HandleMsg::Bury { pet_id: 0 }).unwrap();
+        assert_eq!(oldest_pets(&deps, 0), (vec![(1, born + 100)], 1));
+        assert_eq!(
+            best_caretakers(&deps),
+            vec![(HumanAddr::from("bob"), 0), (HumanAddr::from("alice"), 0)]
+        );
+        // another feeding doesn't change the rounded score, so bob doesn't move
+        food.block.time = carol.block.time;
+        handle(&mut deps, food.clone(), receive_msg("bob", 100, &feed)).unwrap();
+        assert_eq!(
+            best_caretakers(&deps),
+            vec![(HumanAddr::from("bob"), 0), (HumanAddr::from("alice"), 0)]
+        );
+
+        let msg = HandleMsg::SetLeaderboardOptOut { opt_out: true };
+        handle(&mut deps, env.clone(), msg).unwrap();
+        assert_eq!(oldest_pets(&deps, 0), (vec![], 0));
+        assert_eq!(best_caretakers(&deps), vec![(HumanAddr::from("alice"), 0)]);
+
+        let msg = HandleMsg::SetLeaderboardOptOut { opt_out: false };
+        handle(&mut deps, env, msg).unwrap();
+        assert_eq!(oldest_pets(&deps, 0), (vec![(1, born + 100)], 1));
+        // bob is placed again after alice, who already had the same rounded score
+        assert_eq!(
+            best_caretakers(&deps),
+            vec![(HumanAddr::from("alice"), 0), (HumanAddr::from("bob"), 0)]
+        );
+    }
+    #[test]
+    fn test_private_queries() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let pet_info = |address: &str, key: &str| QueryMsg::PetInfo {
+            pet_id: 0,
+            address: HumanAddr::from(address),
+            key: key.to_string(),
+        };
+
+        let res = query(&deps, QueryMsg::PetSummary { pet_id: 0 }).unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::PetSummary { owner, .. } => {
+                assert_eq!(owner, HumanAddr::from("alice"));
+            }
+            _ => panic!("unexpected query response"),
+        }
+
+        let res = query(&deps, pet_info("alice", "wrong")).unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::ViewingKeyError { .. } => {}
+            _ => panic!("a wrong viewing key must not unlock the query"),
+        }
+        set_key(&mut deps, "bob");
+        let res = query(&deps, pet_info("bob", "key"));
+        assert!(res.is_err(), "only the owner can see the private info");
+
+        let msg = HandleMsg::CreateViewingKey {
+            entropy: "entropy".to_string(),
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let key = match from_binary(&res.data.unwrap()).unwrap() {
+            HandleAnswer::CreateViewingKey { key } => key,
+            _ => panic!("unexpected handle answer"),
+        };
+        let res = query(&deps, pet_info("alice", "key")).unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::ViewingKeyError { .. } => {}
+            _ => panic!("the old viewing key is replaced"),
+        }
+        let res = query(&deps, pet_info("alice", &key.to_string())).unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::PetInfoResponse { id, .. } => assert_eq!(id, 0),
+            _ => panic!("unexpected query response"),
+        }
+    }
+    #[test]
+    fn test_pet_metadata() {
+        let mut deps = init_default();
+        let adopt_msg = |name: &str, image_uri: Option<&str>| HandleMsg::Adopt {
+            name: name.to_string(),
+            description: Some("A very hungry fox".to_string()),
+            image_uri: image_uri.map(String::from),
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), adopt_msg("Fo", None));
+        assert!(res.is_err(), "the name is too short");
+        let res = handle(&mut deps, mock_env("alice", &[]), adopt_msg("Foxy!", None));
+        assert!(res.is_err(), "the name has invalid characters");
+        let res = handle(
+            &mut deps,
+            mock_env("alice", &[]),
+            adopt_msg("Foxy", Some("javascript:alert(1)")),
+        );
+        assert!(res.is_err(), "the image uri has an invalid scheme");
+        let msg = adopt_msg("Foxy", Some("ipfs://foxy.png"));
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+
+        let msg = HandleMsg::SetNameConfig {
+            rename_price: Some(Uint128(30)),
+            unique_names: true,
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        let res = handle(&mut deps, mock_env("bob", &[]), adopt_msg("FOXY", None));
+        assert!(res.is_err(), "the name is already taken");
+        handle(&mut deps, mock_env("bob", &[]), adopt_msg("Rex", None)).unwrap();
+
+        let rename = |name: &str| ReceiveMsg::Rename {
+            pet_id: 0,
+            name: name.to_string(),
+            description: None,
+            image_uri: None,
+        };
+        let msg = HandleMsg::Rename {
+            pet_id: 0,
+            name: "Vixen".to_string(),
+            description: None,
+            image_uri: None,
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), msg);
+        assert!(res.is_err(), "renaming costs food");
+        let res = handle(
+            &mut deps,
+            mock_env("food", &[]),
+            receive_msg("bob", 30, &rename("Vixen")),
+        );
+        assert!(res.is_err(), "only the owner can rename the pet");
+        let res = handle(
+            &mut deps,
+            mock_env("food", &[]),
+            receive_msg("alice", 30, &rename("rex")),
+        );
+        assert!(res.is_err(), "the name is already taken");
+        let res = handle(
+            &mut deps,
+            mock_env("food", &[]),
+            receive_msg("alice", 30, &rename("Vixen")),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        let res = query(&deps, QueryMsg::PetSummary { pet_id: 0 }).unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::PetSummary {
+                name,
+                description,
+                image_uri,
+                ..
+            } => {
+                assert_eq!(name, "Vixen");
+                assert_eq!(description, None);
+                assert_eq!(image_uri, None);
+            }
+            _ => panic!("unexpected query response"),
+        }
+        // the old name is free again
+        handle(&mut deps, mock_env("carol", &[]), adopt_msg("Foxy", None)).unwrap();
+    }
+
+    #[test]
+    fn test_nft() {
+        let mut deps = init_default();
+        adopt(&mut deps, "alice");
+        let mut env = mock_env("food", &[]);
+        env.block.time += 3601;
+        let feed = ReceiveMsg::Feed { pet_id: 0 };
+        handle(&mut deps, env.clone(), receive_msg("alice", 100, &feed)).unwrap();
+        let msg = QueryMsg::LastFed {
+            pet_id: 0,
+            address: HumanAddr::from("alice"),
+            key: "key".to_string(),
+        };
+        let last_fed = match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            QueryResponse::LastFedResponse { timestamp } => timestamp,
+            _ => panic!("unexpected query response"),
+        };
+
+        let transfer = |recipient: &str| HandleMsg::TransferNft {
+            recipient: HumanAddr::from(recipient),
+            token_id: "0".to_string(),
+            memo: None,
+        };
+        let res = handle(&mut deps, mock_env("bob", &[]), transfer("carol"));
+        assert!(
+            res.is_err(),
+            "only approved addresses can transfer the token"
+        );
+
+        let msg = HandleMsg::Approve {
+            spender: HumanAddr::from("bob"),
+            token_id: "0".to_string(),
+            expires: Some(Expiration::AtHeight(env.block.height + 10)),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let owner_of = QueryMsg::OwnerOf {
+            token_id: "0".to_string(),
+        };
+        match from_binary(&query(&deps, owner_of.clone()).unwrap()).unwrap() {
+            NftQueryAnswer::OwnerOf { owner, approvals } => {
+                assert_eq!(owner, HumanAddr::from("alice"));
+                assert_eq!(approvals.len(), 1);
+            }
+            _ => panic!("unexpected query response"),
+        }
+        handle(&mut deps, mock_env("bob", &[]), transfer("carol")).unwrap();
+        match from_binary(&query(&deps, owner_of).unwrap()).unwrap() {
+            NftQueryAnswer::OwnerOf { owner, approvals } => {
+                assert_eq!(owner, HumanAddr::from("carol"));
+                assert!(approvals.is_empty(), "approvals don't survive a transfer");
+            }
+            _ => panic!("unexpected query response"),
+        }
+        let res = handle(&mut deps, mock_env("bob", &[]), transfer("bob"));
+        assert!(res.is_err());
+
+        let msg = QueryMsg::Tokens {
+            owner: HumanAddr::from("carol"),
+            start_after: None,
+            limit: None,
+        };
+        match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            NftQueryAnswer::TokenList { tokens } => assert_eq!(tokens, vec!["0".to_string()]),
+            _ => panic!("unexpected query response"),
+        }
+        let msg = QueryMsg::NftInfo {
+            token_id: "0".to_string(),
+        };
+        match from_binary(&query(&deps, msg).unwrap()).unwrap() {
+            NftQueryAnswer::NftInfo { extension, .. } => {
+                let extension = extension.unwrap();
+                assert_eq!(extension.name, Some("Foxy".to_string()));
+                // the stage and achievements would show when the pet was fed
+                let attributes = extension.attributes.unwrap();
+                assert!(attributes.iter().all(|a| {
+                    let trait_type = a.trait_type.as_deref();
+                    trait_type != Some("stage") && trait_type != Some("achievement")
+                }));
+            }
+            _ => panic!("unexpected query response"),
+        }
+
+        // the pet keeps its feedings and stage with its new owner
+        set_key(&mut deps, "carol");
+        let private_metadata = |address: &str| QueryMsg::PrivateMetadata {
+            token_id: "0".to_string(),
+            viewer: Some(ViewerInfo {
+                address: HumanAddr::from(address),
+                viewing_key: "key".to_string(),
+            }),
+        };
+        let res = query(&deps, private_metadata("alice"));
+        assert!(
+            res.is_err(),
+            "the previous owner can't see the private metadata"
+        );
+        let msg = QueryMsg::PrivateMetadata {
+            token_id: "0".to_string(),
+            viewer: None,
+        };
+        assert!(
+            query(&deps, msg).is_err(),
+            "the private metadata needs a viewer"
+        );
+        match from_binary(&query(&deps, private_metadata("carol")).unwrap()).unwrap() {
+            NftQueryAnswer::PrivateMetadata { extension, .. } => {
+                let attributes = extension.unwrap().attributes.unwrap();
+                let value = |trait_type: &str| {
+                    attributes
+                        .iter()
+                        .find(|a| a.trait_type.as_deref() == Some(trait_type))
+                        .map(|a| a.value.clone())
+                };
+                assert_eq!(value("last_fed"), Some(last_fed.to_string()));
+                assert_eq!(value("stage"), Some("baby".to_string()));
+                assert_eq!(value("achievement"), Some("first_feed".to_string()));
+            }
+            _ => panic!("unexpected query response"),
+        }
+
+        let msg = HandleMsg::RegisterReceiveNft {
+            code_hash: "market hash".to_string(),
+            also_implements_batch_receive_nft: None,
+        };
+        handle(&mut deps, mock_env("market", &[]), msg).unwrap();
+        let msg = HandleMsg::SendNft {
+            contract: HumanAddr::from("market"),
+            token_id: "0".to_string(),
+            msg: None,
+            memo: None,
+        };
+        let res = handle(&mut deps, mock_env("carol", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages.len(),
+            1,
+            "the market is told it received the pet"
+        );
+        match from_binary(&res.data.unwrap()).unwrap() {
+            HandleAnswer::SendNft { status } => assert_eq!(status, ResponseStatus::Success),
+            _ => panic!("unexpected handle answer"),
+        }
+    }
+
+    #[test]
+    fn test_nft_operators_and_batches() {
+        let mut deps = init_default();
+        for _ in 0..3 {
+            adopt(&mut deps, "alice");
+        }
+        let transfer = |token_id: &str| HandleMsg::TransferNft {
+            recipient: HumanAddr::from("market"),
+            token_id: token_id.to_string(),
+            memo: None,
+        };
+
+        let msg = HandleMsg::ApproveAll {
+            operator: HumanAddr::from("market"),
+            expires: None,
+        };
+        let res = handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        match from_binary(&res.data.unwrap()).unwrap() {
+            HandleAnswer::ApproveAll { status } => assert_eq!(status, ResponseStatus::Success),
+            _ => panic!("unexpected handle answer"),
+        }
+        handle(&mut deps, mock_env("market", &[]), transfer("0")).unwrap();
+        let msg = HandleMsg::RevokeAll {
+            operator: HumanAddr::from("market"),
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let res = handle(&mut deps, mock_env("market", &[]), transfer("1"));
+        assert!(res.is_err(), "the operator was revoked");
+
+        let whitelist = |view_private_metadata, transfer| HandleMsg::SetWhitelistedApproval {
+            address: HumanAddr::from("market"),
+            token_id: Some("1".to_string()),
+            view_owner: None,
+            view_private_metadata,
+            transfer,
+            expires: None,
+        };
+        let msg = whitelist(Some(AccessLevel::All), None);
+        let res = handle(&mut deps, mock_env("alice", &[]), msg);
+        assert!(res.is_err(), "only the owner sees the private metadata");
+        let msg = whitelist(None, Some(AccessLevel::ApproveToken));
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let res = handle(&mut deps, mock_env("market", &[]), transfer("2"));
+        assert!(res.is_err(), "only pet 1 was whitelisted");
+        let msg = whitelist(None, Some(AccessLevel::None));
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let res = handle(&mut deps, mock_env("market", &[]), transfer("1"));
+        assert!(res.is_err(), "the whitelisting was removed");
+
+        let msg = HandleMsg::BatchTransferNft {
+            transfers: vec![Transfer {
+                recipient: HumanAddr::from("bob"),
+                token_ids: vec!["1".to_string(), "2".to_string()],
+                memo: None,
+            }],
+        };
+        handle(&mut deps, mock_env("alice", &[]), msg).unwrap();
+        let tokens = |deps: &Extern<MockStorage, MockApi, MockQuerier>, owner: &str| {
+            let msg = QueryMsg::Tokens {
+                owner: HumanAddr::from(owner),
+                start_after: None,
+                limit: None,
+            };
+            match from_binary(&query(deps, msg).unwrap()).unwrap() {
+                NftQueryAnswer::TokenList { tokens } => tokens,
+                _ => panic!("unexpected query response"),
+            }
+        };
+        assert_eq!(tokens(&deps, "bob"), vec!["1".to_string(), "2".to_string()]);
+
+        let msg = HandleMsg::RegisterReceiveNft {
+            code_hash: "shelter hash".to_string(),
+            also_implements_batch_receive_nft: Some(true),
+        };
+        handle(&mut deps, mock_env("shelter", &[]), msg).unwrap();
+        let msg = HandleMsg::ApproveAll {
+            operator: HumanAddr::from("bob"),
+            expires: None,
+        };
+        handle(&mut deps, mock_env("market", &[]), msg).unwrap();
+        let msg = HandleMsg::BatchSendNft {
+            sends: vec![Send {
+                contract: HumanAddr::from("shelter"),
+                token_ids: vec!["0".to_string(), "1".to_string(), "2".to_string()],
+                msg: None,
+                memo: None,
+            }],
+        };
+        let res = handle(&mut deps, mock_env("bob", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages.len(),
+            2,
+            "the shelter is told about the pets of the market and of bob"
+        );
+        assert_eq!(tokens(&deps, "shelter").len(), 3);
+    }
+
+    #[test]
+    fn test_breeding() {
+        let mut deps = init_default();
+        let stage = |stage: Stage, min_age: u64| StageConfig {
+            stage,
+            min_age,
+            allowed_feed_timespan: 3600,
+            total_saturation_time: 14200,
+        };
+        let msg = HandleMsg::UpdateConfig {
+            allowed_feed_timespan: None,
+            total_saturation_time: None,
+            stages: Some(vec![stage(Stage::Egg, 0), stage(Stage::Adult, 100)]),
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        adopt(&mut deps, "alice");
+        let msg = HandleMsg::Adopt {
+            name: "Rex".to_string(),
+            description: None,
+            image_uri: None,
+        };
+        handle(&mut deps, mock_env("bob", &[]), msg).unwrap();
+        let breed = |name: &str| HandleMsg::Breed {
+            parent_a: 0,
+            parent_b: 1,
+            name: name.to_string(),
+        };
+        let mut env = mock_env("alice", &[]);
+
+        let res = handle(&mut deps, env.clone(), breed("Kit"));
+        assert!(res.is_err(), "eggs can't breed");
+        env.block.time += 200;
+        let res = handle(&mut deps, env.clone(), breed("Kit"));
+        assert!(res.is_err(), "bob didn't agree yet");
+
+        let partner = HandleMsg::SetBreedingPartner {
+            pet_id: 1,
+            partner: Some(0),
+        };
+        let res = handle(&mut deps, env.clone(), partner.clone());
+        assert!(res.is_err(), "only the owner picks the partner");
+        handle(&mut deps, mock_env("bob", &[]), partner.clone()).unwrap();
+        handle(&mut deps, env.clone(), breed("Kit")).unwrap();
+
+        let res = query(&deps, QueryMsg::PetSummary { pet_id: 2 }).unwrap();
+        match from_binary(&res).unwrap() {
+            QueryResponse::PetSummary {
+                owner,
+                generation,
+                parents,
+                ..
+            } => {
+                assert_eq!(owner, HumanAddr::from("alice"));
+                assert_eq!(generation, 1);
+                assert_eq!(parents, vec![0, 1]);
+            }
+            _ => panic!("unexpected query response"),
+        }
+
+        let res = handle(&mut deps, env.clone(), breed("Kat"));
+        assert!(res.is_err(), "the consent is used up");
+        handle(&mut deps, mock_env("bob", &[]), partner.clone()).unwrap();
+        let res = handle(&mut deps, env.clone(), breed("Kat"));
+        assert!(res.is_err(), "the parents need to rest");
+
+        let msg = HandleMsg::SetBreedingConfig {
+            breed_price: Some(Uint128(50)),
+            breeding_cooldown: 0,
+        };
+        handle(&mut deps, mock_env("sender", &[]), msg).unwrap();
+        let res = handle(&mut deps, env.clone(), breed("Kat"));
+        assert!(res.is_err(), "breeding isn't free anymore");
+        let paid_breed = ReceiveMsg::Breed {
+            parent_a: 0,
+            parent_b: 1,
+            name: "Kat".to_string(),
+        };
+        let mut food_env = env.clone();
+        food_env.message.sender = HumanAddr::from("food");
+        let res = handle(&mut deps, food_env, receive_msg("alice", 50, &paid_breed));
+        assert!(res.is_ok(), "{:?}", res.err());
+
+        // bob agreed to breed with alice's pet, not with whoever owns it next
+        handle(&mut deps, mock_env("bob", &[]), partner).unwrap();
+        let msg = HandleMsg::TransferPet {
+            pet_id: 0,
+            recipient: HumanAddr::from("carol"),
+        };
+        handle(&mut deps, env.clone(), msg).unwrap();
+        let paid_breed = ReceiveMsg::Breed {
+            parent_a: 0,
+            parent_b: 1,
+            name: "Kim".to_string(),
+        };
+        env.message.sender = HumanAddr::from("food");
+        let res = handle(&mut deps, env, receive_msg("carol", 50, &paid_breed));
+        assert!(res.is_err(), "the consent ended with the transfer");
     }
 }
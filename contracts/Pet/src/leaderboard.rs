@@ -0,0 +1,254 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{HumanAddr, ReadonlyStorage, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read, PrefixedStorage, ReadonlyPrefixedStorage};
+
+use secret_toolkit::storage::{TypedStore, TypedStoreMut};
+
+use crate::constants::{
+    CARETAKER_LEADERBOARD_SIZE, LEADERBOARD_CARE_SCORE_STEP, LEADERBOARD_STREAK_STEP,
+};
+use crate::state::{OwnerRecord, Pet};
+
+const PREFIX_RANKED_PETS: &[u8] = b"ranked_pets";
+const RANKED_PET_COUNTS_KEY: &[u8] = b"ranked_pet_counts";
+const PREFIX_DEATH_HOURS: &[u8] = b"death_hours";
+const PREFIX_PET_DEATH_HOUR: &[u8] = b"pet_death_hour";
+const NEXT_DEATH_HOUR_KEY: &[u8] = b"next_death_hour";
+const CARETAKER_RANKING_KEY: &[u8] = b"caretaker_ranking";
+
+// number of pet ids whose ranked flags share a storage entry
+const RANKED_CHUNK_SIZE: u64 = 256;
+// the deaths are grouped by the hour they happen in
+const DEATH_HOUR: u64 = 3600;
+// hours of deaths a message goes through at most, a long quiet time is caught up over a few
+const DEATH_HOURS_PER_MESSAGE: u64 = 24;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LeaderboardOwner {
+    pub owner: HumanAddr,
+    pub care_score: u64,
+    pub best_feeding_streak: u32,
+}
+
+impl LeaderboardOwner {
+    fn ranks_above(&self, other: &LeaderboardOwner) -> bool {
+        (self.care_score, self.best_feeding_streak) > (other.care_score, other.best_feeding_streak)
+    }
+}
+
+// Oldest pets:
+// The ranked pets are the living pets whose owner didn't opt out, in the order they were adopted.
+// Every chunk of 256 pet ids is a bitset in its own entry and the number of ranked pets per chunk
+// is kept in a single list, so a page only reads the chunks it shows.
+
+pub fn set_pet_ranked<S: Storage>(storage: &mut S, pet_id: u64, ranked: bool) -> StdResult<()> {
+    let chunk = pet_id / RANKED_CHUNK_SIZE;
+    let bit = (pet_id % RANKED_CHUNK_SIZE) as usize;
+    let mut bits = read_ranked_chunk(storage, chunk)?;
+    if is_set(&bits, bit) == ranked {
+        return Ok(());
+    }
+    bits[bit / 8] ^= 1 << (bit % 8);
+    let mut chunk_store = PrefixedStorage::new(PREFIX_RANKED_PETS, storage);
+    let mut chunk_store = TypedStoreMut::attach(&mut chunk_store);
+    chunk_store.store(&chunk.to_be_bytes(), &bits)?;
+
+    let mut counts = read_ranked_counts(storage)?;
+    if counts.len() <= chunk as usize {
+        counts.resize(chunk as usize + 1, 0);
+    }
+    if ranked {
+        counts[chunk as usize] += 1;
+    } else {
+        counts[chunk as usize] -= 1;
+    }
+    singleton(storage, RANKED_PET_COUNTS_KEY).save(&counts)
+}
+
+// returns a page of the ranked pet ids, oldest first, and the number of ranked pets
+pub fn get_ranked_pets<S: ReadonlyStorage>(
+    storage: &S,
+    page: u32,
+    page_size: u32,
+) -> StdResult<(Vec<u64>, u64)> {
+    let counts = read_ranked_counts(storage)?;
+    let total = counts.iter().map(|&count| count as u64).sum();
+
+    let mut skip = (page as u64).saturating_mul(page_size as u64);
+    let mut pet_ids = vec![];
+    for (chunk, &count) in counts.iter().enumerate() {
+        if pet_ids.len() >= page_size as usize {
+            break;
+        }
+        // whole chunks before the page are skipped without reading them
+        if skip >= count as u64 {
+            skip -= count as u64;
+            continue;
+        }
+        let bits = read_ranked_chunk(storage, chunk as u64)?;
+        for bit in 0..RANKED_CHUNK_SIZE as usize {
+            if !is_set(&bits, bit) {
+                continue;
+            }
+            if skip > 0 {
+                skip -= 1;
+            } else if pet_ids.len() < page_size as usize {
+                pet_ids.push(chunk as u64 * RANKED_CHUNK_SIZE + bit as u64);
+            }
+        }
+    }
+    Ok((pet_ids, total))
+}
+
+fn read_ranked_chunk<S: ReadonlyStorage>(storage: &S, chunk: u64) -> StdResult<Vec<u8>> {
+    let chunk_store = ReadonlyPrefixedStorage::new(PREFIX_RANKED_PETS, storage);
+    let chunk_store = TypedStore::attach(&chunk_store);
+    Ok(chunk_store
+        .may_load(&chunk.to_be_bytes())?
+        .unwrap_or_else(|| vec![0; RANKED_CHUNK_SIZE as usize / 8]))
+}
+
+fn read_ranked_counts<S: ReadonlyStorage>(storage: &S) -> StdResult<Vec<u16>> {
+    Ok(singleton_read(storage, RANKED_PET_COUNTS_KEY)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+fn is_set(bits: &[u8], bit: usize) -> bool {
+    bits[bit / 8] & (1 << (bit % 8)) != 0
+}
+
+// Deaths:
+// Nothing happens on chain when a pet dies, so every saved pet is listed under the hour it will
+// die in, and the messages unrank the pets of the hours that passed since the previous message.
+// The hours are counted on the pet clock without the contract pauses, which stands still while
+// the contract is paused like the pets do.
+
+pub fn start_death_hours<S: Storage>(storage: &mut S, clock: u64) -> StdResult<()> {
+    singleton(storage, NEXT_DEATH_HOUR_KEY).save(&(clock / DEATH_HOUR))
+}
+
+// lists the pet under the hour of its time of death, pets whose death is recorded aren't listed
+pub fn schedule_pet_death<S: Storage>(storage: &mut S, pet: &Pet) -> StdResult<()> {
+    let next_hour = read_next_death_hour(storage)?;
+    let old_hour = read_pet_death_hour(storage, pet.id)?;
+    let hour = match pet.died_at {
+        Some(_) => None,
+        None => Some(pet.time_of_death().saturating_sub(pet.paused_duration) / DEATH_HOUR),
+    };
+    if hour == old_hour {
+        return Ok(());
+    }
+
+    if let Some(old_hour) = old_hour.filter(|&old_hour| old_hour >= next_hour) {
+        let mut pet_ids = read_death_hour(storage, old_hour)?;
+        pet_ids.retain(|&pet_id| pet_id != pet.id);
+        write_death_hour(storage, old_hour, &pet_ids)?;
+    }
+    let mut hour_store = PrefixedStorage::new(PREFIX_PET_DEATH_HOUR, storage);
+    let mut hour_store = TypedStoreMut::attach(&mut hour_store);
+    match hour {
+        Some(hour) => hour_store.store(&pet.id.to_be_bytes(), &hour)?,
+        None => hour_store.remove(&pet.id.to_be_bytes()),
+    }
+    match hour {
+        // the pet died in an hour that already passed
+        Some(hour) if hour < next_hour => set_pet_ranked(storage, pet.id, false),
+        Some(hour) => {
+            let mut pet_ids = read_death_hour(storage, hour)?;
+            pet_ids.push(pet.id);
+            write_death_hour(storage, hour, &pet_ids)
+        }
+        None => Ok(()),
+    }
+}
+
+// unranks the pets that died in the hours before the clock
+pub fn unrank_dead_pets<S: Storage>(storage: &mut S, clock: u64) -> StdResult<()> {
+    let next_hour = read_next_death_hour(storage)?;
+    let end = std::cmp::min(clock / DEATH_HOUR, next_hour + DEATH_HOURS_PER_MESSAGE);
+    if end <= next_hour {
+        return Ok(());
+    }
+    for hour in next_hour..end {
+        for pet_id in read_death_hour(storage, hour)? {
+            set_pet_ranked(storage, pet_id, false)?;
+        }
+        let mut death_store = PrefixedStorage::new(PREFIX_DEATH_HOURS, storage);
+        death_store.remove(&hour.to_be_bytes());
+    }
+    singleton(storage, NEXT_DEATH_HOUR_KEY).save(&end)
+}
+
+// whether the pet died in an hour the messages already went through
+pub fn is_death_passed<S: ReadonlyStorage>(storage: &S, pet_id: u64) -> StdResult<bool> {
+    let next_hour = read_next_death_hour(storage)?;
+    Ok(matches!(read_pet_death_hour(storage, pet_id)?, Some(hour) if hour < next_hour))
+}
+
+fn read_next_death_hour<S: ReadonlyStorage>(storage: &S) -> StdResult<u64> {
+    singleton_read(storage, NEXT_DEATH_HOUR_KEY).load()
+}
+
+fn read_pet_death_hour<S: ReadonlyStorage>(storage: &S, pet_id: u64) -> StdResult<Option<u64>> {
+    let hour_store = ReadonlyPrefixedStorage::new(PREFIX_PET_DEATH_HOUR, storage);
+    let hour_store = TypedStore::attach(&hour_store);
+    hour_store.may_load(&pet_id.to_be_bytes())
+}
+
+fn read_death_hour<S: ReadonlyStorage>(storage: &S, hour: u64) -> StdResult<Vec<u64>> {
+    let death_store = ReadonlyPrefixedStorage::new(PREFIX_DEATH_HOURS, storage);
+    let death_store = TypedStore::attach(&death_store);
+    Ok(death_store
+        .may_load(&hour.to_be_bytes())?
+        .unwrap_or_default())
+}
+
+fn write_death_hour<S: Storage>(storage: &mut S, hour: u64, pet_ids: &[u64]) -> StdResult<()> {
+    let mut death_store = PrefixedStorage::new(PREFIX_DEATH_HOURS, storage);
+    let mut death_store = TypedStoreMut::attach(&mut death_store);
+    death_store.store(&hour.to_be_bytes(), &pet_ids.to_vec())
+}
+
+// Best caretakers:
+// Only the top caretakers are kept, sorted by care score and best feeding streak. An owner is
+// placed again every time their record is saved, so someone who dropped out of the list comes
+// back with their next feeding or care action. The public list only shows rounded values, an
+// exact score would change with every on-time feeding and tell when the owner is active.
+
+pub fn rank_caretaker<S: Storage>(
+    storage: &mut S,
+    owner: &HumanAddr,
+    record: &OwnerRecord,
+) -> StdResult<()> {
+    let mut ranking = read_caretaker_ranking(storage)?;
+    let entry = LeaderboardOwner {
+        owner: owner.clone(),
+        care_score: record.care_score / LEADERBOARD_CARE_SCORE_STEP * LEADERBOARD_CARE_SCORE_STEP,
+        best_feeding_streak: record.best_feeding_streak / LEADERBOARD_STREAK_STEP
+            * LEADERBOARD_STREAK_STEP,
+    };
+    // an owner whose rounded values didn't change keeps their place, even among ties
+    if !record.leaderboard_opt_out && ranking.contains(&entry) {
+        return Ok(());
+    }
+    ranking.retain(|other| &other.owner != owner);
+    if !record.leaderboard_opt_out {
+        // an owner that reached a rounded score first stays ahead on a tie
+        let position = ranking
+            .iter()
+            .position(|other| entry.ranks_above(other))
+            .unwrap_or(ranking.len());
+        ranking.insert(position, entry);
+        ranking.truncate(CARETAKER_LEADERBOARD_SIZE);
+    }
+    singleton(storage, CARETAKER_RANKING_KEY).save(&ranking)
+}
+
+pub fn read_caretaker_ranking<S: ReadonlyStorage>(storage: &S) -> StdResult<Vec<LeaderboardOwner>> {
+    Ok(singleton_read(storage, CARETAKER_RANKING_KEY)
+        .may_load()?
+        .unwrap_or_default())
+}
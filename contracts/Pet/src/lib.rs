@@ -1,6 +1,9 @@
 pub mod constants;
 pub mod contract;
+pub mod leaderboard;
 pub mod msg;
+pub mod pet_history;
+pub mod receiver;
 pub mod state;
 
 #[cfg(target_arch = "wasm32")]
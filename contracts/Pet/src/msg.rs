@@ -2,7 +2,15 @@ use cosmwasm_std::{Binary, HumanAddr, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::state::SecretToken;
+use secret_toolkit::permit::Permit;
+
+use crate::leaderboard::LeaderboardOwner;
+use crate::pet_history::{Grave, PetHistoryEntry};
+use crate::state::{
+    Achievement, Approval, CareAction, Caretaker, Expiration, FeedingGame, FoodToken, LifeState,
+    SecretToken, Stage, StageConfig, StatDecay, StatEffect, Stats,
+};
+use common::viewing_key::ViewingKey;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InitMsg {
@@ -11,7 +19,26 @@ pub struct InitMsg {
     // ms in epoch time, subtracted from last fed to calculate allowed feeding timespan
     pub allowed_feed_timespan: u64,
     pub total_saturation_time: u64,
+    // seconds of saturation gained per unit of food eaten
+    pub saturation_per_food: u64,
+    // send food the pet can't eat back to the feeder instead of rejecting the feeding
+    pub refund_excess_food: bool,
+    // stages pets evolve through as they age, ordered by min_age.
+    // When not set every stage uses allowed_feed_timespan and total_saturation_time
+    pub stages: Option<Vec<StageConfig>>,
     pub admin: Option<HumanAddr>,
+    // amount of food that has to be paid to adopt a pet, adoption is free if not set
+    pub adoption_price: Option<Uint128>,
+    // maximum number of living pets a single address may own
+    pub max_pets_per_owner: Option<u32>,
+    // points per hour the happiness, health, hygiene and energy stats of new pets lose
+    pub stat_decay: Option<StatDecay>,
+    // amount of food needed to revive a dead pet, reviving is free if not set
+    pub revive_price: Option<Uint128>,
+    // added to the revive price for every previous death of the pet
+    pub revive_price_increase: Option<Uint128>,
+    // seed for the viewing keys created by the contract
+    pub prng_seed: Binary,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -24,26 +51,601 @@ pub enum HandleMsg {
         amount: Uint128,
         msg: Option<Binary>,
     },
+    //adopt a new pet for free, only allowed when no adoption price is set
+    Adopt {
+        name: String,
+        description: Option<String>,
+        image_uri: Option<String>,
+    },
+    //owner only, replaces the name, description and image of the pet. Only allowed when
+    //renaming is free
+    Rename {
+        pet_id: u64,
+        name: String,
+        description: Option<String>,
+        image_uri: Option<String>,
+    },
+    //owner only, hands the pet over to another address and clears its caretakers
+    TransferPet {
+        pet_id: u64,
+        recipient: HumanAddr,
+    },
+    //owner only, lets another address feed the pet until the optional expiration
+    AddCaretaker {
+        pet_id: u64,
+        caretaker: HumanAddr,
+        expiration: Option<u64>,
+    },
+    RemoveCaretaker {
+        pet_id: u64,
+        caretaker: HumanAddr,
+    },
+    //owner only, revives a dead pet for free, only allowed when no revive price is set
+    Revive {
+        pet_id: u64,
+    },
+    //owner of parent_a, breeds two adult pets into an offspring owned by the sender. The owner
+    //of parent_b has to pick parent_a as its breeding partner first, unless the sender owns both.
+    //Only allowed when breeding is free
+    Breed {
+        parent_a: u64,
+        parent_b: u64,
+        name: String,
+    },
+    //owner only, lets the owner of the partner pet breed with this pet once, None withdraws it
+    SetBreedingPartner {
+        pet_id: u64,
+        partner: Option<u64>,
+    },
+    //anyone, records a dead pet in the graveyard
+    Bury {
+        pet_id: u64,
+    },
+    //admin only, the timespans and stages are only changed when set.
+    //Changing the timespans without stages replaces the stages with the default ones
+    UpdateConfig {
+        allowed_feed_timespan: Option<u64>,
+        total_saturation_time: Option<u64>,
+        stages: Option<Vec<StageConfig>>,
+    },
+    ChangeAdmin {
+        address: HumanAddr,
+    },
+    //admin only, registers with the new token the same way init does
+    SetAcceptedToken {
+        accepted_token: SecretToken,
+    },
+    SetContractStatus {
+        level: ContractStatusLevel,
+    },
+    //admin only, registers with the token the same way init does. Adding a token
+    //that is already accepted replaces its nutrition and effects
+    AddFoodToken {
+        food_token: FoodToken,
+    },
+    RemoveFoodToken {
+        address: HumanAddr,
+    },
+    //admin only, turns the feeding game on or off
+    SetFeedingGame {
+        feeding_game: Option<FeedingGame>,
+    },
+    //owner only, the pet doesn't get hungry for duration seconds. Only allowed when sleeping is free
+    Sleep {
+        pet_id: u64,
+        duration: u64,
+    },
+    //owner only, ends the sleep early
+    Wake {
+        pet_id: u64,
+    },
+    //admin only
+    SetSleepConfig {
+        sleep_price: Option<Uint128>,
+        max_sleep_duration: u64,
+        sleep_cooldown: u64,
+    },
+    //owner or caretaker, restores the stat matching the action
+    Care {
+        pet_id: u64,
+        action: CareAction,
+    },
+    //admin only
+    SetAdoptionConfig {
+        adoption_price: Option<Uint128>,
+        max_pets_per_owner: Option<u32>,
+    },
+    //admin only
+    SetReviveConfig {
+        revive_price: Option<Uint128>,
+        revive_price_increase: Uint128,
+    },
+    //admin only
+    SetBreedingConfig {
+        breed_price: Option<Uint128>,
+        breeding_cooldown: u64,
+    },
+    //admin only, unique names only apply to names given after they are turned on
+    SetNameConfig {
+        rename_price: Option<Uint128>,
+        unique_names: bool,
+    },
+    //hides the sender and their pets from the leaderboards, or lists them again
+    SetLeaderboardOptOut {
+        opt_out: bool,
+    },
+    //viewing keys and permits unlock the private queries of the sender
+    CreateViewingKey {
+        entropy: String,
+    },
+    SetViewingKey {
+        key: String,
+    },
+    RevokePermit {
+        permit_name: String,
+    },
+    //SNIP-721 messages, token ids are the pet ids. Transfers work like TransferPet and can also
+    //be sent by approved addresses and operators
+    TransferNft {
+        recipient: HumanAddr,
+        token_id: String,
+        memo: Option<String>,
+    },
+    //transfers the pet and notifies the contract if it registered with RegisterReceiveNft
+    SendNft {
+        contract: HumanAddr,
+        token_id: String,
+        msg: Option<Binary>,
+        memo: Option<String>,
+    },
+    //owner only, lets the spender transfer the pet until it expires, never when not set
+    Approve {
+        spender: HumanAddr,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    Revoke {
+        spender: HumanAddr,
+        token_id: String,
+    },
+    //lets the operator transfer every pet of the sender until it expires, never when not set
+    ApproveAll {
+        operator: HumanAddr,
+        expires: Option<Expiration>,
+    },
+    RevokeAll {
+        operator: HumanAddr,
+    },
+    //only the transfer permission can be granted, ownership is public and the private metadata
+    //is only shown to the owner
+    SetWhitelistedApproval {
+        address: HumanAddr,
+        token_id: Option<String>,
+        view_owner: Option<AccessLevel>,
+        view_private_metadata: Option<AccessLevel>,
+        transfer: Option<AccessLevel>,
+        expires: Option<Expiration>,
+    },
+    BatchTransferNft {
+        transfers: Vec<Transfer>,
+    },
+    BatchSendNft {
+        sends: Vec<Send>,
+    },
+    RegisterReceiveNft {
+        code_hash: String,
+        also_implements_batch_receive_nft: Option<bool>,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleAnswer {
+    CreateViewingKey { key: ViewingKey },
+    TransferNft { status: ResponseStatus },
+    SendNft { status: ResponseStatus },
+    Approve { status: ResponseStatus },
+    Revoke { status: ResponseStatus },
+    ApproveAll { status: ResponseStatus },
+    RevokeAll { status: ResponseStatus },
+    SetWhitelistedApproval { status: ResponseStatus },
+    BatchTransferNft { status: ResponseStatus },
+    BatchSendNft { status: ResponseStatus },
+    RegisterReceiveNft { status: ResponseStatus },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Success,
+    Failure,
+}
+
+// permission levels of SetWhitelistedApproval, in the SNIP-721 format
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessLevel {
+    // approves the address for the token_id
+    ApproveToken,
+    // approves the address for every pet of the owner
+    All,
+    // revokes the approval for the token_id, an approval for all pets stays
+    RevokeToken,
+    // revokes the approval for every pet of the owner and the approvals of single pets
+    None,
+}
+
+// the pets of a BatchTransferNft sent to one recipient
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Transfer {
+    pub recipient: HumanAddr,
+    pub token_ids: Vec<String>,
+    pub memo: Option<String>,
+}
+
+// the pets of a BatchSendNft sent to one contract
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Send {
+    pub contract: HumanAddr,
+    pub token_ids: Vec<String>,
+    pub msg: Option<Binary>,
+    pub memo: Option<String>,
+}
+
+// payload of the `msg` field sent along with food through the snip20 `Send` message
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    Feed {
+        pet_id: u64,
+    },
+    Adopt {
+        name: String,
+        description: Option<String>,
+        image_uri: Option<String>,
+    },
+    Revive {
+        pet_id: u64,
+    },
+    Sleep {
+        pet_id: u64,
+        duration: u64,
+    },
+    Rename {
+        pet_id: u64,
+        name: String,
+        description: Option<String>,
+        image_uri: Option<String>,
+    },
+    Breed {
+        parent_a: u64,
+        parent_b: u64,
+        name: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    LastFed {},
-    PetInfo {},
+    // public, without the feeding times and stats that show when the owner is active
+    PetSummary {
+        pet_id: u64,
+    },
+    // the owner of the pet only, with a viewing key
+    LastFed {
+        pet_id: u64,
+        address: HumanAddr,
+        key: String,
+    },
+    PetInfo {
+        pet_id: u64,
+        address: HumanAddr,
+        key: String,
+    },
+    PetStatus {
+        pet_id: u64,
+        current_time: u64,
+        address: HumanAddr,
+        key: String,
+    },
+    PetHistory {
+        pet_id: u64,
+        page: Option<u32>,
+        page_size: u32,
+        address: HumanAddr,
+        key: String,
+    },
+    OwnerRecord {
+        address: HumanAddr,
+        key: String,
+    },
+    // the same private queries, authenticated with a permit instead of a viewing key
+    WithPermit {
+        permit: Permit,
+        query: QueryWithPermit,
+    },
+    Pets {
+        owner: HumanAddr,
+    },
+    Graveyard {
+        page: Option<u32>,
+        page_size: u32,
+    },
+    OldestPets {
+        page: Option<u32>,
+        page_size: u32,
+    },
+    BestCaretakers {
+        page: Option<u32>,
+        page_size: u32,
+    },
+    Stages {},
+    AdoptionConfig {},
+    ReviveConfig {},
+    SleepConfig {},
+    NameConfig {},
+    BreedingConfig {},
     AcceptedToken {},
+    FoodTokens {},
+    FeedingGame {},
+    ContractStatus {},
+    // SNIP-721 queries, pet ownership is public so only the private metadata needs a viewing key
+    ContractInfo {},
+    NumTokens {},
+    AllTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    Tokens {
+        owner: HumanAddr,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    OwnerOf {
+        token_id: String,
+    },
+    NftInfo {
+        token_id: String,
+    },
+    // the status of the pet, hunger and age are only included when current_time is set
+    PrivateMetadata {
+        token_id: String,
+        viewer: Option<ViewerInfo>,
+    },
+}
+impl QueryMsg {
+    pub fn get_validation_params(&self) -> (&HumanAddr, ViewingKey) {
+        match self {
+            Self::LastFed { address, key, .. } => (address, ViewingKey(key.clone())),
+            Self::PetInfo { address, key, .. } => (address, ViewingKey(key.clone())),
+            Self::PetStatus { address, key, .. } => (address, ViewingKey(key.clone())),
+            Self::PetHistory { address, key, .. } => (address, ViewingKey(key.clone())),
+            Self::OwnerRecord { address, key } => (address, ViewingKey(key.clone())),
+            Self::PrivateMetadata {
+                viewer: Some(viewer),
+                ..
+            } => (&viewer.address, ViewingKey(viewer.viewing_key.clone())),
+            _ => panic!("This query type does not require authentication"),
+        }
+    }
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryWithPermit {
+    LastFed {
+        pet_id: u64,
+    },
+    PetInfo {
+        pet_id: u64,
+    },
+    PetStatus {
+        pet_id: u64,
+        current_time: u64,
+    },
+    PetHistory {
+        pet_id: u64,
+        page: Option<u32>,
+        page_size: u32,
+    },
+    OwnerRecord {},
+    PrivateMetadata {
+        token_id: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ViewerInfo {
+    pub address: HumanAddr,
+    pub viewing_key: String,
+}
+
+#[allow(clippy::large_enum_variant)] // Responses are serialized right away
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
 pub enum QueryResponse {
     LastFedResponse {
         timestamp: u64,
     },
+    PetSummary {
+        id: u64,
+        name: String,
+        description: Option<String>,
+        image_uri: Option<String>,
+        owner: HumanAddr,
+        born_at: u64,
+        deaths: u32,
+        generation: u32,
+        parents: Vec<u64>,
+    },
     PetInfoResponse {
+        id: u64,
+        name: String,
+        description: Option<String>,
+        image_uri: Option<String>,
+        owner: HumanAddr,
+        born_at: u64,
+        // stage as of the last time the pet was fed, PetStatus returns the current one
+        stage: Stage,
         allowed_feed_timespan: u64,
         total_saturation_time: u64,
+        caretakers: Vec<Caretaker>,
+        // stats as of their last update, PetStatus returns the current values
+        stats: Stats,
+        stat_decay: StatDecay,
+        deaths: u32,
+        died_at: Option<u64>,
+        feeding_streak: u32,
+        saturation_penalty: u64,
+        achievements: Vec<Achievement>,
+        generation: u32,
+        parents: Vec<u64>,
+        bred_at: Option<u64>,
+        breeding_partner: Option<u64>,
+    },
+    PetStatusResponse {
+        hunger_percentage: u64,
+        seconds_until_feedable: u64,
+        seconds_until_death: u64,
+        state: LifeState,
+        stage: Stage,
+        age: u64,
+        stats: Stats,
+        sick: bool,
+        // seconds the pet still sleeps, the other values are as of when it wakes up
+        seconds_until_awake: u64,
+    },
+    PetsResponse {
+        pet_ids: Vec<u64>,
+    },
+    OwnerRecord {
+        feeding_streak: u32,
+        best_feeding_streak: u32,
+        care_score: u64,
+        leaderboard_opt_out: bool,
+    },
+    PetHistory {
+        events: Vec<PetHistoryEntry>,
+        total: u64,
+    },
+    Graveyard {
+        graves: Vec<Grave>,
+        total: u64,
+    },
+    OldestPets {
+        pets: Vec<LeaderboardPet>,
+        total: u64,
+    },
+    BestCaretakers {
+        owners: Vec<LeaderboardOwner>,
+        total: u64,
+    },
+    StagesResponse {
+        stages: Vec<StageConfig>,
+    },
+    AdoptionConfig {
+        adoption_price: Option<Uint128>,
+        max_pets_per_owner: Option<u32>,
+    },
+    ReviveConfig {
+        revive_price: Option<Uint128>,
+        revive_price_increase: Uint128,
+    },
+    SleepConfig {
+        sleep_price: Option<Uint128>,
+        max_sleep_duration: u64,
+        sleep_cooldown: u64,
+    },
+    NameConfig {
+        rename_price: Option<Uint128>,
+        unique_names: bool,
+    },
+    BreedingConfig {
+        breed_price: Option<Uint128>,
+        breeding_cooldown: u64,
     },
     AcceptedToken {
         address: HumanAddr,
         hash: String,
     },
+    FoodTokens {
+        food_tokens: Vec<FoodTokenInfo>,
+    },
+    FeedingGame {
+        feeding_game: Option<FeedingGame>,
+    },
+    ContractStatus {
+        status: ContractStatusLevel,
+    },
+    ViewingKeyError {
+        msg: String,
+    },
+}
+
+// answers of the SNIP-721 queries, in the format NFT marketplaces expect
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum NftQueryAnswer {
+    ContractInfo {
+        name: String,
+        symbol: String,
+    },
+    NumTokens {
+        count: u32,
+    },
+    TokenList {
+        tokens: Vec<String>,
+    },
+    OwnerOf {
+        owner: HumanAddr,
+        approvals: Vec<Approval>,
+    },
+    NftInfo {
+        token_uri: Option<String>,
+        extension: Option<Extension>,
+    },
+    PrivateMetadata {
+        token_uri: Option<String>,
+        extension: Option<Extension>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Extension {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub attributes: Option<Vec<Trait>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Trait {
+    pub display_type: Option<String>,
+    pub trait_type: Option<String>,
+    pub value: String,
+}
+
+// a food token without its viewing key
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct FoodTokenInfo {
+    pub address: HumanAddr,
+    pub hash: String,
+    pub nutrition: u64,
+    pub effects: Vec<StatEffect>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LeaderboardPet {
+    pub pet_id: u64,
+    pub name: String,
+    pub owner: HumanAddr,
+    pub born_at: u64,
+}
+
+// while stopped only feeding and care are allowed, so pets don't die because of a paused contract
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractStatusLevel {
+    NormalRun,
+    StopAllButFeeding,
+    StopAll,
 }
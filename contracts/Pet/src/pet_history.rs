@@ -0,0 +1,152 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{BlockInfo, HumanAddr, ReadonlyStorage, StdResult, Storage, Uint128};
+use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
+
+use secret_toolkit::storage::{AppendStore, AppendStoreMut};
+
+use crate::state::{Pet, Stage};
+
+const PREFIX_PET_HISTORY: &[u8] = b"pet_history";
+const PREFIX_GRAVEYARD: &[u8] = b"graveyard";
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PetEvent {
+    Born {
+        owner: HumanAddr,
+    },
+    Fed {
+        feeder: HumanAddr,
+        food_eaten: Uint128,
+    },
+    Evolved {
+        stage: Stage,
+    },
+    // died_at is the time the pet actually died, which can be before the event was recorded
+    Died {
+        died_at: u64,
+    },
+    Revived {
+        deaths: u32,
+    },
+    Transferred {
+        from: HumanAddr,
+        recipient: HumanAddr,
+    },
+    Renamed {
+        name: String,
+    },
+    // stored for both parents
+    Bred {
+        partner: u64,
+        offspring: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct PetHistoryEntry {
+    pub event: PetEvent,
+    pub block_time: u64,
+    pub block_height: u64,
+}
+
+// A pet is added to the graveyard every time it dies, revived pets keep their old graves
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct Grave {
+    pub pet_id: u64,
+    pub name: String,
+    pub owner: HumanAddr,
+    pub born_at: u64,
+    pub died_at: u64,
+    pub stage: Stage,
+}
+
+// Storage functions:
+
+pub fn store_pet_event<S: Storage>(
+    store: &mut S,
+    pet_id: u64,
+    event: PetEvent,
+    block: &BlockInfo,
+) -> StdResult<()> {
+    let entry = PetHistoryEntry {
+        event,
+        block_time: block.time,
+        block_height: block.height,
+    };
+    let mut store =
+        PrefixedStorage::multilevel(&[PREFIX_PET_HISTORY, &pet_id.to_be_bytes()], store);
+    let mut store = AppendStoreMut::attach_or_create(&mut store)?;
+    store.push(&entry)
+}
+
+pub fn store_grave<S: Storage>(store: &mut S, pet: &Pet, died_at: u64) -> StdResult<()> {
+    let grave = Grave {
+        pet_id: pet.id,
+        name: pet.name.clone(),
+        owner: pet.owner.clone(),
+        born_at: pet.adopted_at,
+        died_at,
+        stage: pet.stage,
+    };
+    let mut store = PrefixedStorage::new(PREFIX_GRAVEYARD, store);
+    let mut store = AppendStoreMut::attach_or_create(&mut store)?;
+    store.push(&grave)
+}
+
+pub fn get_pet_history<S: ReadonlyStorage>(
+    storage: &S,
+    pet_id: u64,
+    page: u32,
+    page_size: u32,
+) -> StdResult<(Vec<PetHistoryEntry>, u64)> {
+    let store =
+        ReadonlyPrefixedStorage::multilevel(&[PREFIX_PET_HISTORY, &pet_id.to_be_bytes()], storage);
+
+    // Try to access the history of the pet.
+    // If it doesn't exist yet, return an empty list of events.
+    let store = AppendStore::<PetHistoryEntry, _, _>::attach(&store);
+    let store = if let Some(result) = store {
+        result?
+    } else {
+        return Ok((vec![], 0));
+    };
+
+    // Take `page_size` events starting from the latest one, potentially skipping
+    // `page * page_size` events from the start. A page past the end is just empty.
+    let events: StdResult<Vec<PetHistoryEntry>> = store
+        .iter()
+        .rev()
+        .skip((page as usize).saturating_mul(page_size as usize))
+        .take(page_size as _)
+        .collect();
+    events.map(|events| (events, store.len() as u64))
+}
+
+pub fn get_graves<S: ReadonlyStorage>(
+    storage: &S,
+    page: u32,
+    page_size: u32,
+) -> StdResult<(Vec<Grave>, u64)> {
+    let store = ReadonlyPrefixedStorage::new(PREFIX_GRAVEYARD, storage);
+
+    let store = AppendStore::<Grave, _, _>::attach(&store);
+    let store = if let Some(result) = store {
+        result?
+    } else {
+        return Ok((vec![], 0));
+    };
+
+    // latest graves first, like the pet history
+    let graves: StdResult<Vec<Grave>> = store
+        .iter()
+        .rev()
+        .skip((page as usize).saturating_mul(page_size as usize))
+        .take(page_size as _)
+        .collect();
+    graves.map(|graves| (graves, store.len() as u64))
+}
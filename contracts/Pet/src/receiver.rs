@@ -0,0 +1,107 @@
+#![allow(clippy::field_reassign_with_default)] // This is triggered in `#[derive(JsonSchema)]`
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{to_binary, Binary, CosmosMsg, HumanAddr, StdResult, WasmMsg};
+
+use common::utils::space_pad;
+
+use crate::constants::RESPONSE_BLOCK_SIZE;
+
+/// Snip721ReceiveMsg is sent to contracts that registered with RegisterReceiveNft when
+/// pet tokens of the same previous owner are sent to them
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct Snip721ReceiveMsg {
+    pub sender: HumanAddr,
+    pub from: HumanAddr,
+    pub token_ids: Vec<String>,
+    pub msg: Option<Binary>,
+}
+
+impl Snip721ReceiveMsg {
+    pub fn new(
+        sender: HumanAddr,
+        from: HumanAddr,
+        token_ids: Vec<String>,
+        msg: Option<Binary>,
+    ) -> Self {
+        Self {
+            sender,
+            from,
+            token_ids,
+            msg,
+        }
+    }
+
+    /// serializes the messages, and pads them to 256 bytes. BatchReceiveNft carries all the
+    /// tokens and the sender, ReceiveNft only carries one token and the previous owner, so
+    /// there is one for every token
+    pub fn into_binaries(self, batch: bool) -> StdResult<Vec<Binary>> {
+        let msgs = if batch {
+            vec![ReceiverHandleMsg::BatchReceiveNft {
+                sender: self.sender,
+                from: self.from,
+                token_ids: self.token_ids,
+                msg: self.msg,
+            }]
+        } else {
+            let (from, msg) = (self.from, self.msg);
+            self.token_ids
+                .into_iter()
+                .map(|token_id| ReceiverHandleMsg::ReceiveNft {
+                    sender: from.clone(),
+                    token_id,
+                    msg: msg.clone(),
+                })
+                .collect()
+        };
+        msgs.iter()
+            .map(|msg| {
+                let mut data = to_binary(msg)?;
+                space_pad(RESPONSE_BLOCK_SIZE, &mut data.0);
+                Ok(data)
+            })
+            .collect()
+    }
+
+    /// creates the cosmos_msgs sending this struct to the named contract
+    pub fn into_cosmos_msgs(
+        self,
+        batch: bool,
+        callback_code_hash: String,
+        contract_addr: HumanAddr,
+    ) -> StdResult<Vec<CosmosMsg>> {
+        let msgs = self.into_binaries(batch)?;
+        Ok(msgs
+            .into_iter()
+            .map(|msg| {
+                WasmMsg::Execute {
+                    msg,
+                    callback_code_hash: callback_code_hash.clone(),
+                    contract_addr: contract_addr.clone(),
+                    send: vec![],
+                }
+                .into()
+            })
+            .collect())
+    }
+}
+
+// This is just a helper to properly serialize the above message
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+enum ReceiverHandleMsg {
+    ReceiveNft {
+        sender: HumanAddr,
+        token_id: String,
+        msg: Option<Binary>,
+    },
+    BatchReceiveNft {
+        sender: HumanAddr,
+        from: HumanAddr,
+        token_ids: Vec<String>,
+        msg: Option<Binary>,
+    },
+}
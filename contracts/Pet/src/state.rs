@@ -1,10 +1,31 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Env, HumanAddr, Storage};
-use cosmwasm_storage::{singleton, singleton_read, ReadonlySingleton, Singleton};
+use cosmwasm_std::{
+    BlockInfo, Env, HumanAddr, ReadonlyStorage, StdError, StdResult, Storage, Uint128,
+};
+use cosmwasm_storage::{
+    singleton, singleton_read, PrefixedStorage, ReadonlyPrefixedStorage, ReadonlySingleton,
+    Singleton,
+};
+use secret_toolkit::storage::{TypedStore, TypedStoreMut};
+
+use crate::constants::{
+    ACHIEVEMENT_STREAK_DURATION, DEFAULT_STAGE_AGES, MAX_STAT_VALUE, SICK_HEALTH_VALUE,
+    STARVING_HUNGER_PERCENTAGE,
+};
+use crate::leaderboard::{rank_caretaker, schedule_pet_death};
+use crate::msg::ContractStatusLevel;
+use common::viewing_key::ViewingKey;
 
 pub static CONFIG_KEY: &[u8] = b"config";
+pub const PREFIX_PETS: &[u8] = b"pets";
+pub const PREFIX_OWNED_PETS: &[u8] = b"owned_pets";
+pub const PREFIX_OWNER_RECORDS: &[u8] = b"owner_records";
+pub const PREFIX_VIEW_KEY: &[u8] = b"viewingkey";
+pub const PREFIX_PET_NAMES: &[u8] = b"pet_names";
+pub const PREFIX_NFT_RECEIVERS: &[u8] = b"nft_receivers";
+pub const PREFIX_OPERATORS: &[u8] = b"operators";
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone, JsonSchema)]
 pub struct SecretToken {
@@ -12,32 +33,777 @@ pub struct SecretToken {
     pub hash: String,
     pub viewing_key: String,
 }
+// a snip20 token pets can eat besides the accepted token, the accepted token is the only
+// one that can be used to pay
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, JsonSchema)]
+pub struct FoodToken {
+    pub token: SecretToken,
+    // percentage of saturation_per_food a unit of this food gives, the accepted token gives 100
+    pub nutrition: u64,
+    // stats raised every time a pet eats this food
+    pub effects: Vec<StatEffect>,
+}
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, JsonSchema)]
+pub struct StatEffect {
+    pub stat: StatKind,
+    pub points: u64,
+}
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Pet {
+    pub id: u64,
+    pub name: String,
+    pub description: Option<String>,
+    pub image_uri: Option<String>,
+    pub owner: HumanAddr,
+    // moved by the contract pauses and sleep like the other timestamps, so the age stands still
+    pub born_at: u64,
+    // time the pet was adopted or bred, the birth time shown to others
+    pub adopted_at: u64,
+    pub stage: Stage,
     pub last_fed: u64,
+    // taken from the config of the current stage
+    pub allowed_feed_timespan: u64,
+    pub total_saturation_time: u64,
+    // addresses the owner allowed to feed the pet
+    pub caretakers: Vec<Caretaker>,
+    // addresses the owner allowed to transfer the pet token, cleared when the pet is transferred
+    pub approvals: Vec<Approval>,
+    pub stats: Stats,
+    // taken from the config when the pet was adopted
+    pub stat_decay: StatDecay,
+    // number of times the pet died and was revived
+    pub deaths: u32,
+    // set once the death of the pet is recorded in the graveyard, cleared when it's revived
+    pub died_at: Option<u64>,
+    // contract pause time already added to the timestamps of the pet
+    pub paused_duration: u64,
+    // end of the latest sleep, the pet is asleep until then
+    pub woke_at: Option<u64>,
+    // number of on-time feedings in a row
+    pub feeding_streak: u32,
+    // time of the first feeding of the current streak
+    pub streak_started_at: u64,
+    // seconds overfeeding took off the total_saturation_time of the stages
+    pub saturation_penalty: u64,
+    pub achievements: Vec<Achievement>,
+    // adopted pets are generation 0 without parents, bred pets are one generation after
+    // their older parent
+    pub generation: u32,
+    pub parents: Vec<u64>,
+    // end of the latest breeding, the breeding cooldown starts from there
+    pub bred_at: Option<u64>,
+    // pet whose owner may breed with this pet once, set by the owner of this pet
+    pub breeding_partner: Option<BreedingPartner>,
+}
+// the agreement only holds while the partner has the owner it had when it was picked,
+// so it doesn't pass on to whoever the partner is transferred to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BreedingPartner {
+    pub pet_id: u64,
+    pub owner: HumanAddr,
+}
+// set at adoption and when the pet is renamed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PetMetadata {
+    pub name: String,
+    pub description: Option<String>,
+    pub image_uri: Option<String>,
+}
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Egg,
+    Baby,
+    Child,
+    Adult,
+    Elder,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StageConfig {
+    pub stage: Stage,
+    // age in seconds at which a pet reaches this stage
+    pub min_age: u64,
     pub allowed_feed_timespan: u64,
     pub total_saturation_time: u64,
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Stat {
+    // between 0 and MAX_STAT_VALUE at the time of the last update
+    pub value: u64,
+    pub updated_at: u64,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Stats {
+    pub happiness: Stat,
+    pub health: Stat,
+    pub hygiene: Stat,
+    pub energy: Stat,
+}
+// points each stat loses per hour
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatDecay {
+    pub happiness: u64,
+    pub health: u64,
+    pub hygiene: u64,
+    pub energy: u64,
+}
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StatKind {
+    Happiness,
+    Health,
+    Hygiene,
+    Energy,
+}
+// restores one of the stats back to MAX_STAT_VALUE
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CareAction {
+    Play,
+    Medicine,
+    Clean,
+    Rest,
+}
+// optional game mode, without it feeding before the feeding window is rejected
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeedingGame {
+    // feeding before the window opens costs health and saturation time
+    pub overfeeding_health_penalty: u64,
+    pub overfeeding_saturation_penalty: u64,
+    // feedings within this many seconds after the window opens extend the feeding streak
+    // and take back the saturation penalty
+    pub bonus_window: u64,
+    pub bonus_happiness: u64,
+}
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Achievement {
+    FirstFeed,
+    // on-time feedings for a week without breaking the streak
+    WeekStreak,
+    ReachedAdult,
+    Revived,
+}
+// feeding and care record of an owner over all of their pets
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct OwnerRecord {
+    // number of on-time feedings in a row, of any of the pets of the owner
+    pub feeding_streak: u32,
+    pub best_feeding_streak: u32,
+    pub care_score: u64,
+    // hides the owner and their pets from the leaderboards
+    pub leaderboard_opt_out: bool,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Caretaker {
+    pub address: HumanAddr,
+    pub expiration: Option<u64>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Approval {
+    pub spender: HumanAddr,
+    pub expires: Expiration,
+}
+// same format as the expirations of SNIP-721 contracts
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Expiration {
+    AtHeight(u64),
+    AtTime(u64),
+    Never,
+}
+// contract that gets a ReceiveNft message when a pet token is sent to it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NftReceiver {
+    pub code_hash: String,
+    pub also_implements_batch_receive_nft: bool,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LifeState {
+    // fed recently, can't be fed yet
+    Full,
+    Hungry,
+    // less than a quarter of the saturation is left
+    Starving,
+    Asleep,
+    Dead,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
     pub accepted_token: SecretToken,
-    pub pet: Pet,
+    // other tokens pets can be fed with
+    pub food_tokens: Vec<FoodToken>,
     pub admin: Option<HumanAddr>,
+    // defaults used for the stages when none are configured
+    pub allowed_feed_timespan: u64,
+    pub total_saturation_time: u64,
+    // ordered by min_age, the first stage starts at age 0
+    pub stages: Vec<StageConfig>,
+    pub saturation_per_food: u64,
+    pub refund_excess_food: bool,
+    // number of pets created so far, also used as the id of the next pet
+    pub pet_count: u64,
+    pub adoption_price: Option<Uint128>,
+    pub max_pets_per_owner: Option<u32>,
+    pub stat_decay: StatDecay,
+    // amount of food needed to revive a dead pet, reviving is free if not set
+    pub revive_price: Option<Uint128>,
+    // added to the revive price for every previous death of the pet
+    pub revive_price_increase: Uint128,
+    pub contract_status: ContractStatusLevel,
+    // set while the contract is stopped with StopAll, which freezes the clock of the pets
+    pub paused_at: Option<u64>,
+    // total time the contract spent paused, pets are shifted by it when they are loaded
+    pub paused_duration: u64,
+    // amount of food a pet's sleep costs, sleeping is free if not set
+    pub sleep_price: Option<Uint128>,
+    pub max_sleep_duration: u64,
+    // seconds after waking up before a pet can sleep again
+    pub sleep_cooldown: u64,
+    pub feeding_game: Option<FeedingGame>,
+    // hashed seed for the viewing keys
+    pub prng_seed: Vec<u8>,
+    // permits have to be signed for this address
+    pub contract_address: HumanAddr,
+    // amount of food renaming a pet costs, renaming is free if not set
+    pub rename_price: Option<Uint128>,
+    // when set, no two pets can have the same name, ignoring case
+    pub unique_names: bool,
+    // amount of food breeding costs, breeding is free if not set
+    pub breed_price: Option<Uint128>,
+    // seconds after breeding before a parent can breed again
+    pub breeding_cooldown: u64,
 }
 
 impl Pet {
     pub fn is_dead(&self, env: &Env) -> bool {
-        if env.block.time > self.last_fed + self.total_saturation_time {
+        self.is_dead_at(env.block.time)
+    }
+    pub fn can_be_fed(&self, env: &Env) -> bool {
+        self.can_be_fed_at(env.block.time)
+    }
+    // queries have no Env, so the time based checks are also exposed for an explicit time
+    pub fn is_dead_at(&self, time: u64) -> bool {
+        if time > self.starves_after() {
             return true;
         }
-        false
+        // a pet that was never given medicine eventually dies of illness
+        self.stats.health.value_at(time, self.stat_decay.health) == 0
     }
-    pub fn can_be_fed(&self, env: &Env) -> bool {
+    // the last moment the pet is still fed, a huge saturation time doesn't overflow
+    fn starves_after(&self) -> u64 {
+        self.last_fed.saturating_add(self.total_saturation_time)
+    }
+    // the first moment the pet counts as dead, either starved or out of health
+    pub fn time_of_death(&self) -> u64 {
+        let starved_at = self.starves_after().saturating_add(1);
+        match self.stats.health.empty_at(self.stat_decay.health) {
+            Some(empty_at) => std::cmp::min(starved_at, empty_at),
+            None => starved_at,
+        }
+    }
+    // sick when the health is low or the pet is neglected in more than one way
+    pub fn is_sick_at(&self, time: u64) -> bool {
+        let stats = self.stats_at(time);
+        let neglected = [stats.happiness, stats.hygiene, stats.energy]
+            .iter()
+            .filter(|stat| stat.value == 0)
+            .count();
+        stats.health.value < SICK_HEALTH_VALUE || neglected >= 2
+    }
+    pub fn stats_at(&self, time: u64) -> Stats {
+        let decay = &self.stat_decay;
+        Stats {
+            happiness: self.stats.happiness.decayed(time, decay.happiness),
+            health: self.stats.health.decayed(time, decay.health),
+            hygiene: self.stats.hygiene.decayed(time, decay.hygiene),
+            energy: self.stats.energy.decayed(time, decay.energy),
+        }
+    }
+    pub fn care(&mut self, action: CareAction, time: u64) {
+        *self.stats.get_mut(action.stat()) = Stat::full(time);
+    }
+    pub fn lower_stat(&mut self, kind: StatKind, points: u64, time: u64) {
+        let decay_per_hour = self.stat_decay.get(kind);
+        let stat = self.stats.get_mut(kind);
+        *stat = Stat {
+            value: stat.value_at(time, decay_per_hour).saturating_sub(points),
+            updated_at: time,
+        };
+    }
+    pub fn raise_stat(&mut self, kind: StatKind, points: u64, time: u64) {
+        let decay_per_hour = self.stat_decay.get(kind);
+        let stat = self.stats.get_mut(kind);
+        let value = stat.value_at(time, decay_per_hour).saturating_add(points);
+        *stat = Stat {
+            value: std::cmp::min(value, MAX_STAT_VALUE),
+            updated_at: time,
+        };
+    }
+    pub fn can_be_fed_at(&self, time: u64) -> bool {
         let feeding_timestamp = self.last_fed + self.allowed_feed_timespan;
-        let current_timestamp = env.block.time;
 
-        current_timestamp > feeding_timestamp
-            && current_timestamp < self.last_fed + self.total_saturation_time
+        time > feeding_timestamp && time < self.starves_after()
+    }
+    pub fn hunger_percentage(&self, time: u64) -> u64 {
+        if self.total_saturation_time == 0 {
+            return 100;
+        }
+        let hunger =
+            time.saturating_sub(self.last_fed) as u128 * 100 / self.total_saturation_time as u128;
+        std::cmp::min(hunger, 100) as u64
+    }
+    pub fn seconds_until_feedable(&self, time: u64) -> u64 {
+        (self.last_fed + self.allowed_feed_timespan + 1).saturating_sub(time)
+    }
+    pub fn seconds_until_death(&self, time: u64) -> u64 {
+        let starves_in = self.starves_after().saturating_sub(time);
+        match self.stats.health.empty_at(self.stat_decay.health) {
+            Some(empty_at) => std::cmp::min(starves_in, empty_at.saturating_sub(time)),
+            None => starves_in,
+        }
+    }
+    pub fn life_state(&self, time: u64) -> LifeState {
+        if self.is_dead_at(time) {
+            LifeState::Dead
+        } else if self.is_asleep_at(time) {
+            LifeState::Asleep
+        } else if !self.can_be_fed_at(time) {
+            LifeState::Full
+        } else if self.hunger_percentage(time) >= STARVING_HUNGER_PERCENTAGE {
+            LifeState::Starving
+        } else {
+            LifeState::Hungry
+        }
+    }
+    // amount of food needed to fully saturate the pet at the given time
+    pub fn food_needed(&self, env: &Env, saturation_per_food: u64) -> u128 {
+        let missing_saturation = env.block.time.saturating_sub(self.last_fed) as u128;
+        let per_food = saturation_per_food as u128;
+        let food = missing_saturation / per_food;
+        if food * per_food < missing_saturation {
+            food + 1
+        } else {
+            food
+        }
+    }
+    // adds saturation for the eaten food, capped by total_saturation_time
+    pub fn eat(&mut self, env: &Env, food: u128, saturation_per_food: u64) {
+        let missing_saturation = env.block.time.saturating_sub(self.last_fed);
+        let gained = food.saturating_mul(saturation_per_food as u128);
+        self.last_fed += std::cmp::min(gained, missing_saturation as u128) as u64;
+    }
+    // moves the pet to the stage matching its age and takes over the timespans of the stage,
+    // so config updates reach existing pets. Returns true if the stage changed.
+    // A dead pet doesn't evolve, so a longer saturation time can't bring it back to life
+    pub fn evolve(&mut self, time: u64, stages: &[StageConfig]) -> bool {
+        if self.is_dead_at(time) || self.is_asleep_at(time) {
+            return false;
+        }
+        let age = time.saturating_sub(self.born_at);
+        let stage_config = match stages.iter().rev().find(|s| s.min_age <= age) {
+            Some(stage_config) => stage_config,
+            None => return false,
+        };
+        let evolved = stage_config.stage != self.stage;
+
+        self.stage = stage_config.stage;
+        self.allowed_feed_timespan = stage_config.allowed_feed_timespan;
+        self.total_saturation_time = stage_config
+            .total_saturation_time
+            .saturating_sub(self.saturation_penalty);
+        evolved
+    }
+    pub fn in_bonus_window(&self, time: u64, bonus_window: u64) -> bool {
+        let window_opens = self.last_fed + self.allowed_feed_timespan;
+        self.can_be_fed_at(time) && time <= window_opens + bonus_window
+    }
+    // With the feeding game a feeding is on time within the bonus window, otherwise any
+    // feeding before the pet starts starving is. Called before the pet eats
+    pub fn is_fed_on_time(&self, time: u64, game: Option<&FeedingGame>) -> bool {
+        match game {
+            Some(game) => self.in_bonus_window(time, game.bonus_window),
+            None => {
+                self.can_be_fed_at(time)
+                    && self.hunger_percentage(time) < STARVING_HUNGER_PERCENTAGE
+            }
+        }
+    }
+    pub fn record_feeding(&mut self, on_time: bool, time: u64) {
+        if !on_time {
+            self.feeding_streak = 0;
+            return;
+        }
+        if self.feeding_streak == 0 {
+            self.streak_started_at = time;
+        }
+        self.feeding_streak += 1;
+    }
+    // adds the achievement if the pet doesn't have it yet, returns true if it was added
+    pub fn unlock(&mut self, achievement: Achievement) -> bool {
+        if self.achievements.contains(&achievement) {
+            return false;
+        }
+        self.achievements.push(achievement);
+        true
+    }
+    // unlocks the achievements the pet earned by now and returns the new ones
+    pub fn unlock_achievements(&mut self, time: u64) -> Vec<Achievement> {
+        let mut earned = vec![];
+        if self.feeding_streak > 0
+            && time.saturating_sub(self.streak_started_at) >= ACHIEVEMENT_STREAK_DURATION
+        {
+            earned.push(Achievement::WeekStreak);
+        }
+        if self.stage == Stage::Adult || self.stage == Stage::Elder {
+            earned.push(Achievement::ReachedAdult);
+        }
+        if self.deaths > 0 {
+            earned.push(Achievement::Revived);
+        }
+        earned.retain(|achievement| self.unlock(*achievement));
+        earned
+    }
+    // called before the pet eats, as the feeding time is compared to the previous feeding
+    pub fn play_feeding_game(&mut self, time: u64, game: &FeedingGame) {
+        if !self.can_be_fed_at(time) {
+            self.lower_stat(StatKind::Health, game.overfeeding_health_penalty, time);
+            self.saturation_penalty += game.overfeeding_saturation_penalty;
+            self.total_saturation_time = self
+                .total_saturation_time
+                .saturating_sub(game.overfeeding_saturation_penalty);
+        } else if self.in_bonus_window(time, game.bonus_window) {
+            self.raise_stat(StatKind::Happiness, game.bonus_happiness, time);
+            self.total_saturation_time += self.saturation_penalty;
+            self.saturation_penalty = 0;
+        }
+    }
+    // moves the timestamps of the pet forward by the time the contract was paused since the
+    // pet was last loaded, so pauses count neither towards hunger and stats nor towards age
+    pub fn sync_pause(&mut self, paused_duration: u64) {
+        let shift = paused_duration.saturating_sub(self.paused_duration);
+        self.shift_clock(shift);
+        self.woke_at = self.woke_at.map(|woke_at| woke_at + shift);
+        self.bred_at = self.bred_at.map(|bred_at| bred_at + shift);
+        self.paused_duration = paused_duration;
+    }
+    fn shift_clock(&mut self, shift: u64) {
+        self.born_at += shift;
+        self.last_fed += shift;
+        self.streak_started_at += shift;
+        for stat in self.stats.iter_mut() {
+            stat.updated_at += shift;
+        }
+    }
+    fn unshift_clock(&mut self, shift: u64) {
+        self.born_at -= shift;
+        self.last_fed -= shift;
+        self.streak_started_at -= shift;
+        for stat in self.stats.iter_mut() {
+            stat.updated_at -= shift;
+        }
+    }
+    pub fn is_asleep_at(&self, time: u64) -> bool {
+        match self.woke_at {
+            Some(woke_at) => time < woke_at,
+            None => false, // never slept
+        }
+    }
+    pub fn sleep_left(&self, time: u64) -> u64 {
+        self.woke_at
+            .map_or(0, |woke_at| woke_at.saturating_sub(time))
+    }
+    // The timestamps of a sleeping pet are moved past the end of the sleep, so hunger, stats
+    // and age stand still until the pet wakes up and it can't be fed or die in the meantime
+    pub fn sleep(&mut self, time: u64, duration: u64) {
+        self.shift_clock(duration);
+        self.woke_at = Some(time + duration);
+    }
+    // wakes the pet before the end of its sleep, giving back the time it didn't sleep
+    pub fn wake(&mut self, time: u64) {
+        self.unshift_clock(self.sleep_left(time));
+        self.woke_at = Some(time);
+    }
+    pub fn can_sleep_at(&self, time: u64, cooldown: u64) -> bool {
+        match self.woke_at {
+            Some(woke_at) => time >= woke_at + cooldown,
+            None => true,
+        }
+    }
+    pub fn can_breed_at(&self, time: u64, cooldown: u64) -> bool {
+        match self.bred_at {
+            Some(bred_at) => time >= bred_at + cooldown,
+            None => true,
+        }
+    }
+    // brings a dead pet back with full saturation and stats
+    pub fn revive(&mut self, time: u64) {
+        self.last_fed = time;
+        self.stats = Stats::full(time);
+        self.deaths += 1;
+        self.died_at = None;
+        self.feeding_streak = 0;
+        self.saturation_penalty = 0;
+    }
+    pub fn can_be_fed_by(&self, address: &HumanAddr, block: &BlockInfo) -> bool {
+        &self.owner == address
+            || self
+                .caretakers
+                .iter()
+                .any(|caretaker| &caretaker.address == address && !caretaker.is_expired_at(block))
+    }
+    pub fn can_be_transferred_by(&self, address: &HumanAddr, block: &BlockInfo) -> bool {
+        &self.owner == address
+            || self
+                .approvals
+                .iter()
+                .any(|approval| &approval.spender == address && !approval.expires.is_expired(block))
+    }
+}
+
+impl State {
+    // the accepted token is also a food, with the default nutrition and no effects
+    pub fn food_token(&self, address: &HumanAddr) -> Option<FoodToken> {
+        if &self.accepted_token.address == address {
+            return Some(FoodToken {
+                token: self.accepted_token.clone(),
+                nutrition: 100,
+                effects: vec![],
+            });
+        }
+        self.food_tokens
+            .iter()
+            .find(|food| &food.token.address == address)
+            .cloned()
+    }
+    // the time the clock of the pets is at, frozen while the contract is paused
+    pub fn pet_time(&self, time: u64) -> u64 {
+        match self.paused_at {
+            Some(paused_at) => std::cmp::min(time, paused_at),
+            None => time,
+        }
+    }
+    // the time minus all the pauses, it stands still while paused and never jumps when resuming
+    pub fn running_time(&self, time: u64) -> u64 {
+        self.pet_time(time).saturating_sub(self.paused_duration)
+    }
+    pub fn pause(&mut self, time: u64) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(time);
+        }
+    }
+    pub fn unpause(&mut self, time: u64) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_duration += time.saturating_sub(paused_at);
+        }
+    }
+    // the price escalates with every previous death of the pet
+    pub fn revive_price_for(&self, pet: &Pet) -> StdResult<Option<Uint128>> {
+        match self.revive_price {
+            Some(price) => self
+                .revive_price_increase
+                .u128()
+                .checked_mul(pet.deaths as u128)
+                .and_then(|increase| price.u128().checked_add(increase))
+                .map(|price| Some(Uint128(price)))
+                .ok_or_else(|| StdError::generic_err("The revive price overflows. ")),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Stat {
+    pub fn full(time: u64) -> Self {
+        Stat {
+            value: MAX_STAT_VALUE,
+            updated_at: time,
+        }
+    }
+    pub fn value_at(&self, time: u64, decay_per_hour: u64) -> u64 {
+        let elapsed = time.saturating_sub(self.updated_at) as u128;
+        let decayed = elapsed * decay_per_hour as u128 / 3600;
+        (self.value as u128).saturating_sub(decayed) as u64
+    }
+    pub fn decayed(&self, time: u64, decay_per_hour: u64) -> Stat {
+        Stat {
+            value: self.value_at(time, decay_per_hour),
+            updated_at: time,
+        }
+    }
+    // time at which the stat reaches 0, None if it doesn't decay
+    pub fn empty_at(&self, decay_per_hour: u64) -> Option<u64> {
+        if decay_per_hour == 0 {
+            return None;
+        }
+        let needed = self.value as u128 * 3600;
+        let elapsed = needed / decay_per_hour as u128;
+        let elapsed = if elapsed * (decay_per_hour as u128) < needed {
+            elapsed + 1
+        } else {
+            elapsed
+        };
+        Some(self.updated_at + elapsed as u64)
+    }
+}
+
+impl Stats {
+    pub fn full(time: u64) -> Self {
+        Stats {
+            happiness: Stat::full(time),
+            health: Stat::full(time),
+            hygiene: Stat::full(time),
+            energy: Stat::full(time),
+        }
+    }
+    pub fn get_mut(&mut self, kind: StatKind) -> &mut Stat {
+        match kind {
+            StatKind::Happiness => &mut self.happiness,
+            StatKind::Health => &mut self.health,
+            StatKind::Hygiene => &mut self.hygiene,
+            StatKind::Energy => &mut self.energy,
+        }
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Stat> {
+        vec![
+            &mut self.happiness,
+            &mut self.health,
+            &mut self.hygiene,
+            &mut self.energy,
+        ]
+        .into_iter()
+    }
+}
+
+impl StatDecay {
+    pub fn get(&self, kind: StatKind) -> u64 {
+        match kind {
+            StatKind::Happiness => self.happiness,
+            StatKind::Health => self.health,
+            StatKind::Hygiene => self.hygiene,
+            StatKind::Energy => self.energy,
+        }
+    }
+    // every decay rate is taken from one of the parents and can mutate by a point per hour
+    pub fn inherit(a: &StatDecay, b: &StatDecay, random: &[u8; 32]) -> StatDecay {
+        let gene = |i: usize, a: u64, b: u64| {
+            let decay = if random[i] < 128 { a } else { b };
+            match random[i + 4] % 3 {
+                0 => decay.saturating_sub(1),
+                1 => decay,
+                _ => decay + 1,
+            }
+        };
+        StatDecay {
+            happiness: gene(0, a.happiness, b.happiness),
+            health: gene(1, a.health, b.health),
+            hygiene: gene(2, a.hygiene, b.hygiene),
+            energy: gene(3, a.energy, b.energy),
+        }
+    }
+}
+
+impl CareAction {
+    pub fn stat(&self) -> StatKind {
+        match self {
+            CareAction::Play => StatKind::Happiness,
+            CareAction::Medicine => StatKind::Health,
+            CareAction::Clean => StatKind::Hygiene,
+            CareAction::Rest => StatKind::Energy,
+        }
+    }
+    pub fn as_str(&self) -> &str {
+        match self {
+            CareAction::Play => "play",
+            CareAction::Medicine => "medicine",
+            CareAction::Clean => "clean",
+            CareAction::Rest => "rest",
+        }
+    }
+}
+
+impl LifeState {
+    pub fn as_str(&self) -> &str {
+        match self {
+            LifeState::Full => "full",
+            LifeState::Hungry => "hungry",
+            LifeState::Starving => "starving",
+            LifeState::Asleep => "asleep",
+            LifeState::Dead => "dead",
+        }
+    }
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Stage::Egg => "egg",
+            Stage::Baby => "baby",
+            Stage::Child => "child",
+            Stage::Adult => "adult",
+            Stage::Elder => "elder",
+        }
+    }
+}
+
+pub fn default_stages(allowed_feed_timespan: u64, total_saturation_time: u64) -> Vec<StageConfig> {
+    let stages = [
+        Stage::Egg,
+        Stage::Baby,
+        Stage::Child,
+        Stage::Adult,
+        Stage::Elder,
+    ];
+    stages
+        .iter()
+        .zip(DEFAULT_STAGE_AGES.iter())
+        .map(|(stage, min_age)| StageConfig {
+            stage: *stage,
+            min_age: *min_age,
+            allowed_feed_timespan,
+            total_saturation_time,
+        })
+        .collect()
+}
+
+impl Achievement {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Achievement::FirstFeed => "first_feed",
+            Achievement::WeekStreak => "week_streak",
+            Achievement::ReachedAdult => "reached_adult",
+            Achievement::Revived => "revived",
+        }
+    }
+}
+
+impl OwnerRecord {
+    pub fn record_feeding(&mut self, on_time: bool) {
+        if on_time {
+            self.feeding_streak += 1;
+            self.best_feeding_streak = std::cmp::max(self.best_feeding_streak, self.feeding_streak);
+        } else {
+            self.feeding_streak = 0;
+        }
+    }
+}
+
+impl Caretaker {
+    pub fn is_expired_at(&self, block: &BlockInfo) -> bool {
+        match self.expiration {
+            Some(time) => block.time >= time,
+            None => false, // caretaker has no expiration
+        }
+    }
+}
+
+impl Expiration {
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        match self {
+            Expiration::AtHeight(height) => block.height >= *height,
+            Expiration::AtTime(time) => block.time >= *time,
+            Expiration::Never => false,
+        }
     }
 }
 
@@ -45,6 +811,173 @@ pub fn config<S: Storage>(storage: &mut S) -> Singleton<S, State> {
     singleton(storage, CONFIG_KEY)
 }
 
-pub fn config_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, State> {
+pub fn config_read<S: ReadonlyStorage>(storage: &S) -> ReadonlySingleton<S, State> {
     singleton_read(storage, CONFIG_KEY)
 }
+
+// Pets
+
+// keeps the time of death of the pet listed for the oldest pets leaderboard
+pub fn save_pet<S: Storage>(storage: &mut S, pet: &Pet) -> StdResult<()> {
+    let mut pet_store = PrefixedStorage::new(PREFIX_PETS, storage);
+    let mut pet_store = TypedStoreMut::attach(&mut pet_store);
+    pet_store.store(&pet.id.to_be_bytes(), pet)?;
+    schedule_pet_death(storage, pet)
+}
+
+pub fn may_load_pet<S: ReadonlyStorage>(storage: &S, pet_id: u64) -> StdResult<Option<Pet>> {
+    let pet_store = ReadonlyPrefixedStorage::new(PREFIX_PETS, storage);
+    let pet_store = TypedStore::attach(&pet_store);
+    pet_store.may_load(&pet_id.to_be_bytes())
+}
+
+// loads the pet with the contract pauses applied to its timestamps
+pub fn load_pet<S: ReadonlyStorage>(storage: &S, pet_id: u64) -> StdResult<Pet> {
+    let mut pet = may_load_pet(storage, pet_id)?
+        .ok_or_else(|| StdError::generic_err(format!("Pet with id {} does not exist. ", pet_id)))?;
+    let state = config_read(storage).load()?;
+    pet.sync_pause(state.paused_duration);
+    Ok(pet)
+}
+
+// Owner index
+
+pub fn read_owned_pets<S: ReadonlyStorage>(storage: &S, owner: &HumanAddr) -> StdResult<Vec<u64>> {
+    let owner_store = ReadonlyPrefixedStorage::new(PREFIX_OWNED_PETS, storage);
+    let owner_store = TypedStore::attach(&owner_store);
+    owner_store
+        .may_load(owner.as_str().as_bytes())
+        .map(Option::unwrap_or_default)
+}
+
+pub fn write_owned_pets<S: Storage>(
+    storage: &mut S,
+    owner: &HumanAddr,
+    pet_ids: &[u64],
+) -> StdResult<()> {
+    let mut owner_store = PrefixedStorage::new(PREFIX_OWNED_PETS, storage);
+    let mut owner_store = TypedStoreMut::attach(&mut owner_store);
+    owner_store.store(owner.as_str().as_bytes(), &pet_ids.to_vec())
+}
+
+pub fn add_owned_pet<S: Storage>(storage: &mut S, owner: &HumanAddr, pet_id: u64) -> StdResult<()> {
+    let mut pet_ids = read_owned_pets(storage, owner)?;
+    pet_ids.push(pet_id);
+    write_owned_pets(storage, owner, &pet_ids)
+}
+
+pub fn remove_owned_pet<S: Storage>(
+    storage: &mut S,
+    owner: &HumanAddr,
+    pet_id: u64,
+) -> StdResult<()> {
+    let mut pet_ids = read_owned_pets(storage, owner)?;
+    pet_ids.retain(|id| *id != pet_id);
+    write_owned_pets(storage, owner, &pet_ids)
+}
+
+// Owner records
+
+pub fn load_owner_record<S: ReadonlyStorage>(
+    storage: &S,
+    owner: &HumanAddr,
+) -> StdResult<OwnerRecord> {
+    let record_store = ReadonlyPrefixedStorage::new(PREFIX_OWNER_RECORDS, storage);
+    let record_store = TypedStore::attach(&record_store);
+    record_store
+        .may_load(owner.as_str().as_bytes())
+        .map(Option::unwrap_or_default)
+}
+
+pub fn save_owner_record<S: Storage>(
+    storage: &mut S,
+    owner: &HumanAddr,
+    record: &OwnerRecord,
+) -> StdResult<()> {
+    let mut record_store = PrefixedStorage::new(PREFIX_OWNER_RECORDS, storage);
+    let mut record_store = TypedStoreMut::attach(&mut record_store);
+    record_store.store(owner.as_str().as_bytes(), record)?;
+    // the best caretakers leaderboard follows every change of the record
+    rank_caretaker(storage, owner, record)
+}
+
+// Name index, names are stored in lower case so uniqueness ignores case
+
+pub fn read_pet_name<S: ReadonlyStorage>(storage: &S, name: &str) -> StdResult<Option<u64>> {
+    let name_store = ReadonlyPrefixedStorage::new(PREFIX_PET_NAMES, storage);
+    let name_store = TypedStore::attach(&name_store);
+    name_store.may_load(name.to_lowercase().as_bytes())
+}
+
+pub fn write_pet_name<S: Storage>(storage: &mut S, name: &str, pet_id: u64) -> StdResult<()> {
+    let mut name_store = PrefixedStorage::new(PREFIX_PET_NAMES, storage);
+    let mut name_store = TypedStoreMut::attach(&mut name_store);
+    name_store.store(name.to_lowercase().as_bytes(), &pet_id)
+}
+
+pub fn remove_pet_name<S: Storage>(storage: &mut S, name: &str, pet_id: u64) -> StdResult<()> {
+    // without unique names another pet may have taken over the name in the index
+    if read_pet_name(storage, name)? != Some(pet_id) {
+        return Ok(());
+    }
+    let mut name_store = PrefixedStorage::new(PREFIX_PET_NAMES, storage);
+    let mut name_store: TypedStoreMut<u64, _> = TypedStoreMut::attach(&mut name_store);
+    name_store.remove(name.to_lowercase().as_bytes());
+    Ok(())
+}
+
+// Viewing keys
+
+pub fn write_viewing_key<S: Storage>(store: &mut S, owner: &HumanAddr, key: &ViewingKey) {
+    let mut key_store = PrefixedStorage::new(PREFIX_VIEW_KEY, store);
+    key_store.set(owner.as_str().as_bytes(), &key.to_hashed());
+}
+
+pub fn read_viewing_key<S: ReadonlyStorage>(store: &S, owner: &HumanAddr) -> Option<Vec<u8>> {
+    let key_store = ReadonlyPrefixedStorage::new(PREFIX_VIEW_KEY, store);
+    key_store.get(owner.as_str().as_bytes())
+}
+
+// Contracts registered to receive pet tokens
+
+pub fn read_nft_receiver<S: ReadonlyStorage>(
+    storage: &S,
+    contract: &HumanAddr,
+) -> StdResult<Option<NftReceiver>> {
+    let receiver_store = ReadonlyPrefixedStorage::new(PREFIX_NFT_RECEIVERS, storage);
+    let receiver_store = TypedStore::attach(&receiver_store);
+    receiver_store.may_load(contract.as_str().as_bytes())
+}
+
+pub fn write_nft_receiver<S: Storage>(
+    storage: &mut S,
+    contract: &HumanAddr,
+    receiver: &NftReceiver,
+) -> StdResult<()> {
+    let mut receiver_store = PrefixedStorage::new(PREFIX_NFT_RECEIVERS, storage);
+    let mut receiver_store = TypedStoreMut::attach(&mut receiver_store);
+    receiver_store.store(contract.as_str().as_bytes(), receiver)
+}
+
+// Operators approved by an owner to transfer all of their pets
+
+pub fn read_operators<S: ReadonlyStorage>(
+    storage: &S,
+    owner: &HumanAddr,
+) -> StdResult<Vec<Approval>> {
+    let operator_store = ReadonlyPrefixedStorage::new(PREFIX_OPERATORS, storage);
+    let operator_store = TypedStore::attach(&operator_store);
+    Ok(operator_store
+        .may_load(owner.as_str().as_bytes())?
+        .unwrap_or_default())
+}
+
+pub fn write_operators<S: Storage>(
+    storage: &mut S,
+    owner: &HumanAddr,
+    operators: &[Approval],
+) -> StdResult<()> {
+    let mut operator_store = PrefixedStorage::new(PREFIX_OPERATORS, storage);
+    let mut operator_store = TypedStoreMut::attach(&mut operator_store);
+    operator_store.store(owner.as_str().as_bytes(), &operators.to_vec())
+}
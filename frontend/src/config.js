@@ -8,6 +8,7 @@ const config = {
   food_addr: "secret153prsqhskssqarz7guwszehdpgf74g2n40up0f",
   market_addr: "secret1jskvqghxl0r024dtgpe2lnalmcpgy5j37zs6u2",
   pet_addr: "secret18j00x0qt38unlapsk3e8m9feg7adsl9su00wan",
+  pet_id: 0,
   viewing_key: "api_key_UNkhX6E508u+GF7nNYuP6N+TPfj0Vo2JaUljSCVAJK8="
 };
 
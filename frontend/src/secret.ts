@@ -54,22 +54,64 @@ const getClient = async () => {
   return client;
 };
 
+const petViewingKeyItem = (address: string) =>
+  `pet_viewing_key_${config.pet_addr}_${address}`;
+
 export class SecretAPI {
   private client: SigningCosmWasmClient;
   private total_saturation_time: number = 0;
   constructor() {
-    getClient().then(async (client) => {
-      this.client = client;
-      const time = await this.getPetInfo();
-      this.total_saturation_time = time;
-    });
+    getClient()
+      .then(async (client) => {
+        this.client = client;
+        const time = await this.getPetInfo();
+        this.total_saturation_time = time;
+      })
+      .catch((error) => console.error("Couldn't load the pet info:", error));
   }
   async getPetInfo() {
-    const response = await this.client.queryContractSmart(config.pet_addr, {
-      pet_info: {},
-    });
+    const response = await this.queryPet("pet_info", { pet_id: config.pet_id });
     return response.PetInfoResponse.total_saturation_time;
   }
+  // Private pet queries are sent with the viewing key of the wallet. The key is created by the
+  // Pet contract the first time it's needed and kept in the browser for that address only.
+  // A key that was replaced since is created again
+  async queryPet(query: string, params: object) {
+    const address = this.client.senderAddress;
+    const msg = (key: string) => ({ [query]: { ...params, address, key } });
+    let response = await this.client.queryContractSmart(
+      config.pet_addr,
+      msg(await this.getPetViewingKey(address))
+    );
+    if (response.ViewingKeyError) {
+      localStorage.removeItem(petViewingKeyItem(address));
+      response = await this.client.queryContractSmart(
+        config.pet_addr,
+        msg(await this.getPetViewingKey(address))
+      );
+    }
+    if (response.ViewingKeyError) {
+      throw new Error(response.ViewingKeyError.msg);
+    }
+    return response;
+  }
+  async getPetViewingKey(address: string) {
+    const stored = localStorage.getItem(petViewingKeyItem(address));
+    if (stored) {
+      return stored;
+    }
+    const entropy = new Uint8Array(32);
+    crypto.getRandomValues(entropy);
+    const result = await this.client.execute(config.pet_addr, {
+      create_viewing_key: {
+        entropy: btoa(String.fromCharCode(...Array.from(entropy))),
+      },
+    });
+    const answer = JSON.parse(new TextDecoder().decode(result.data));
+    const key = answer.create_viewing_key.key;
+    localStorage.setItem(petViewingKeyItem(address), key);
+    return key;
+  }
   async buyFood(amount: string) {
     const msg = {
       buy_food: {},
@@ -86,6 +128,7 @@ export class SecretAPI {
       send: {
         recipient: config.pet_addr,
         amount: amount.toString(),
+        msg: btoa(JSON.stringify({ feed: { pet_id: config.pet_id } })),
       },
     };
     const response = await this.client.execute(config.food_addr, msg);
@@ -93,18 +136,12 @@ export class SecretAPI {
   }
 
   async getSaturationLevel() {
-    const msg = {
-      last_fed: {},
-    };
-    const response = await this.client.queryContractSmart(config.pet_addr, msg);
-    const last_fed = response.LastFedResponse.timestamp;
-    const current_time = Math.floor(Date.now() / 1000);
-    const remaining = current_time - last_fed;
-    const percentage = Math.ceil(
-      100 - Math.max((remaining / this.total_saturation_time) * 100, 0)
-    );
+    const response = await this.queryPet("pet_status", {
+      pet_id: config.pet_id,
+      current_time: Math.floor(Date.now() / 1000),
+    });
 
-    return percentage;
+    return 100 - response.PetStatusResponse.hunger_percentage;
   }
 
   async getFoodBalance() {
@@ -0,0 +1,3 @@
+pub mod rand;
+pub mod utils;
+pub mod viewing_key;
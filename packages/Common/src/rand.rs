@@ -9,7 +9,7 @@ pub fn sha_256(data: &[u8]) -> [u8; 32] {
     let hash = hasher.finalize();
 
     let mut result = [0u8; 32];
-    result.copy_from_slice(hash.as_slice());
+    result.copy_from_slice(&hash);
     result
 }
 
@@ -22,12 +22,12 @@ impl Prng {
         let mut hasher = Sha256::new();
 
         // write input message
-        hasher.update(&seed);
-        hasher.update(&entropy);
+        hasher.update(seed);
+        hasher.update(entropy);
         let hash = hasher.finalize();
 
         let mut hash_bytes = [0u8; 32];
-        hash_bytes.copy_from_slice(hash.as_slice());
+        hash_bytes.copy_from_slice(&hash);
 
         let rng: ChaChaRng = ChaChaRng::from_seed(hash_bytes);
 
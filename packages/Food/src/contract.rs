@@ -6,13 +6,16 @@ use cosmwasm_std::{
     StdResult, Storage, Uint128,
 };
 
+use common::rand::sha_256;
+use common::utils::space_pad;
+use common::viewing_key::{ViewingKey, VIEWING_KEY_SIZE};
+
 use crate::batch;
 use crate::msg::QueryWithPermit;
 use crate::msg::{
-    space_pad, ContractStatusLevel, HandleAnswer, HandleMsg, InitMsg, QueryAnswer, QueryMsg,
+    ContractStatusLevel, HandleAnswer, HandleMsg, InitMsg, QueryAnswer, QueryMsg,
     ResponseStatus::Success,
 };
-use crate::rand::sha_256;
 use crate::receiver::Snip20ReceiveMsg;
 use crate::state::{
     get_receiver_hash, read_allowance, read_viewing_key, set_receiver_hash, write_allowance,
@@ -21,7 +24,6 @@ use crate::state::{
 use crate::transaction_history::{
     get_transfers, get_txs, store_burn, store_deposit, store_mint, store_redeem, store_transfer,
 };
-use crate::viewing_key::{ViewingKey, VIEWING_KEY_SIZE};
 use secret_toolkit::permit::{validate, Permission, Permit, RevokedPermits};
 
 /// We make sure that responses from `handle` are padded to a multiple of this size.
@@ -938,7 +940,7 @@ fn try_add_receiver_api_callback<S: ReadonlyStorage>(
     if let Some(receiver_hash) = recipient_code_hash {
         let receiver_msg = Snip20ReceiveMsg::new(sender, from, amount, memo, msg);
         let callback_msg = receiver_msg.into_cosmos_msg(receiver_hash, recipient)?;
-        
+
         messages.push(callback_msg);
         return Ok(());
     }
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::batch;
 use crate::transaction_history::{RichTx, Tx};
-use crate::viewing_key::ViewingKey;
+use common::viewing_key::ViewingKey;
 use cosmwasm_std::{Binary, HumanAddr, StdError, StdResult, Uint128};
 use secret_toolkit::permit::Permit;
 use secret_toolkit::utils::InitCallback;
@@ -478,20 +478,6 @@ pub fn u8_to_status_level(status_level: u8) -> StdResult<ContractStatusLevel> {
     }
 }
 
-// Take a Vec<u8> and pad it up to a multiple of `block_size`, using spaces at the end.
-pub fn space_pad(block_size: usize, message: &mut Vec<u8>) -> &mut Vec<u8> {
-    let len = message.len();
-    let surplus = len % block_size;
-    if surplus == 0 {
-        return message;
-    }
-
-    let missing = block_size - surplus;
-    message.reserve(missing);
-    message.extend(std::iter::repeat(b' ').take(missing));
-    message
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -7,9 +7,18 @@ rm -rf artifacts; mkdir artifacts
 echo "Building  and optimizing contracts"
 
 
+ROOT="$(pwd)"
+
 for DIR in contracts packages; do
+    # the optimizer only sees the contract directory, so the shared crate is mounted
+    # where the contract's path dependency points to
+    case "$DIR" in
+        contracts) COMMON_MOUNT=/packages/Common ;;
+        packages) COMMON_MOUNT=/Common ;;
+    esac
     for D in "$DIR"/*; do
-        if [ -d "$D" ]; then
+        # Common is a library used by the contracts, not a contract itself
+        if [ -d "$D" ] && [ "$D" != "packages/Common" ]; then
             (
                 cd "$D"
                 BASENAME="$(basename "$D")"
@@ -19,6 +28,7 @@ for DIR in contracts packages; do
 
                 echo "Optimzing $D"
                 docker run --rm -v "$(pwd)":/contract \
+                    -v "$ROOT/packages/Common":"$COMMON_MOUNT" \
                     --mount type=volume,source="$(basename "$(pwd)")_cache",target=/code/target \
                     --mount type=volume,source=registry_cache,target=/usr/local/cargo/registry \
                      enigmampc/secret-contract-optimizer:1.0.5